@@ -0,0 +1,96 @@
+use raylib::math::Rectangle;
+
+#[derive(Clone, Copy)]
+pub enum Arrange {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    CenterX,
+    CenterY,
+    DistributeX,
+    DistributeY,
+}
+//new corner positions for the components (index, rect), same order as given
+pub fn arrange(op: Arrange, rects: &[(usize, Rectangle)]) -> Vec<(usize, f32, f32)> {
+    if rects.len() < 2 {
+        return Vec::new();
+    }
+    let left = rects.iter().map(|(_, r)| r.x).fold(f32::INFINITY, f32::min);
+    let top = rects.iter().map(|(_, r)| r.y).fold(f32::INFINITY, f32::min);
+    let right = rects.iter().map(|(_, r)| r.x + r.width).fold(f32::NEG_INFINITY, f32::max);
+    let bottom = rects.iter().map(|(_, r)| r.y + r.height).fold(f32::NEG_INFINITY, f32::max);
+    let center_x = (left + right) / 2.0;
+    let center_y = (top + bottom) / 2.0;
+    match op {
+        Arrange::Left => rects.iter().map(|(i, r)| (*i, left, r.y)).collect(),
+        Arrange::Right => rects.iter().map(|(i, r)| (*i, right - r.width, r.y)).collect(),
+        Arrange::Top => rects.iter().map(|(i, r)| (*i, r.x, top)).collect(),
+        Arrange::Bottom => rects.iter().map(|(i, r)| (*i, r.x, bottom - r.height)).collect(),
+        Arrange::CenterX => rects.iter().map(|(i, r)| (*i, center_x - r.width / 2.0, r.y)).collect(),
+        Arrange::CenterY => rects.iter().map(|(i, r)| (*i, r.x, center_y - r.height / 2.0)).collect(),
+        //equal gaps between neighbours, the outermost two stay put
+        Arrange::DistributeX => {
+            let mut sorted = rects.to_vec();
+            sorted.sort_by(|(_, a), (_, b)| a.x.total_cmp(&b.x));
+            let used: f32 = sorted.iter().map(|(_, r)| r.width).sum();
+            let gap = (right - left - used) / (sorted.len() - 1) as f32;
+            let mut x = left;
+            sorted
+                .into_iter()
+                .map(|(i, r)| {
+                    let pos = (i, x, r.y);
+                    x += r.width + gap;
+                    pos
+                })
+                .collect()
+        }
+        Arrange::DistributeY => {
+            let mut sorted = rects.to_vec();
+            sorted.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+            let used: f32 = sorted.iter().map(|(_, r)| r.height).sum();
+            let gap = (bottom - top - used) / (sorted.len() - 1) as f32;
+            let mut y = top;
+            sorted
+                .into_iter()
+                .map(|(i, r)| {
+                    let pos = (i, r.x, y);
+                    y += r.height + gap;
+                    pos
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rects() -> Vec<(usize, Rectangle)> {
+        vec![
+            (0, Rectangle::new(0.0, 5.0, 10.0, 10.0)),
+            (1, Rectangle::new(100.0, 0.0, 10.0, 20.0)),
+            (2, Rectangle::new(30.0, 40.0, 20.0, 10.0)),
+        ]
+    }
+
+    #[test]
+    fn aligning_keeps_the_other_axis() {
+        assert_eq!(arrange(Arrange::Left, &rects()), vec![(0, 0.0, 5.0), (1, 0.0, 0.0), (2, 0.0, 40.0)]);
+        assert_eq!(arrange(Arrange::Right, &rects()), vec![(0, 100.0, 5.0), (1, 100.0, 0.0), (2, 90.0, 40.0)]);
+        assert_eq!(arrange(Arrange::Bottom, &rects()), vec![(0, 0.0, 40.0), (1, 100.0, 30.0), (2, 30.0, 40.0)]);
+        assert_eq!(arrange(Arrange::CenterY, &rects()), vec![(0, 0.0, 20.0), (1, 100.0, 15.0), (2, 30.0, 20.0)]);
+    }
+    #[test]
+    fn distributing_evens_out_the_gaps() {
+        //10 + 20 + 10 wide over 110, so 35 between each
+        assert_eq!(arrange(Arrange::DistributeX, &rects()), vec![(0, 0.0, 5.0), (2, 45.0, 40.0), (1, 100.0, 0.0)]);
+        //20 + 10 + 10 high over 50, so 5 between each
+        assert_eq!(arrange(Arrange::DistributeY, &rects()), vec![(1, 100.0, 0.0), (0, 0.0, 25.0), (2, 30.0, 40.0)]);
+    }
+    #[test]
+    fn one_component_stays_put() {
+        assert!(arrange(Arrange::Left, &rects()[..1]).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use raylib::core::math::Vector2;
+use serde_json::{json, Value};
+use slslib::sls::ID;
+
+pub const SLOTS: usize = 9;
+
+//a saved camera position, target is the world point in the middle of the screen
+pub struct Bookmark {
+    pub name: String,
+    pub target: Vector2,
+    pub zoom: f32,
+    //IDs of the ICs drilled into, outermost first
+    pub ic_path: Vec<ID>,
+}
+pub type Bookmarks = [Option<Bookmark>; SLOTS];
+
+//prog.slj -> prog.views.json
+pub fn sidecar_path(circuit_path: &str) -> PathBuf {
+    Path::new(circuit_path).with_extension("views.json")
+}
+fn parse(slot: &Value) -> Option<(usize, Bookmark)> {
+    let index = slot.get("slot")?.as_u64()? as usize;
+    let target = slot.get("target")?.as_array()?;
+    let bookmark = Bookmark {
+        name: slot.get("name")?.as_str()?.to_string(),
+        target: Vector2::new(target.first()?.as_f64()? as f32, target.get(1)?.as_f64()? as f32),
+        zoom: slot.get("zoom")?.as_f64()? as f32,
+        ic_path: serde_json::from_value(slot.get("ic_path")?.clone()).ok()?,
+    };
+    (1..=SLOTS).contains(&index).then_some((index - 1, bookmark))
+}
+pub fn load(circuit_path: &str) -> Bookmarks {
+    let mut bookmarks: Bookmarks = Default::default();
+    let path = sidecar_path(circuit_path);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return bookmarks;
+    };
+    let views: Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("couldn't read {}: {}", path.display(), e);
+            return bookmarks;
+        }
+    };
+    for slot in views.get("views").and_then(Value::as_array).into_iter().flatten() {
+        match parse(slot) {
+            Some((i, bookmark)) => bookmarks[i] = Some(bookmark),
+            None => println!("skipping bad view in {}: {}", path.display(), slot),
+        }
+    }
+    bookmarks
+}
+pub fn save(circuit_path: &str, bookmarks: &Bookmarks) -> Result<(), String> {
+    let views: Vec<Value> = bookmarks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, b)| b.as_ref().map(|b| (i, b)))
+        .map(|(i, b)| {
+            json!({
+                "slot": i + 1,
+                "name": b.name,
+                "target": [b.target.x, b.target.y],
+                "zoom": b.zoom,
+                "ic_path": b.ic_path,
+            })
+        })
+        .collect();
+    let path = sidecar_path(circuit_path);
+    let text = serde_json::to_string_pretty(&json!({ "views": views })).expect("views to json");
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, text))
+        .map_err(|e| format!("couldn't save {}: {}", path.display(), e))
+}
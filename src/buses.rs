@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use slslib::sls::ID;
+
+//fewer parallel wires than this are left alone by the automatic grouping
+const MIN_AUTO_BITS: usize = 2;
+
+//what the user said about the wires between two components
+pub struct BusRule {
+    //IDs of the ICs drilled into, outermost first
+    pub ic_path: Vec<ID>,
+    pub src: ID,
+    pub sink: ID,
+    //true to always draw them as one bus, false to never
+    pub grouped: bool,
+}
+//parallel wires from src into sink drawn as one line, all indexes
+pub struct Bus {
+    pub src: usize,
+    pub sink: usize,
+    //sink input pins, least significant bit first
+    pub bits: Vec<usize>,
+}
+//one wire as (src, out pin, sink, in pin)
+pub type Wire = (usize, usize, usize, usize);
+
+//prog.slj -> prog.buses.json
+pub fn sidecar_path(circuit_path: &str) -> PathBuf {
+    Path::new(circuit_path).with_extension("buses.json")
+}
+fn parse(rule: &Value) -> Option<BusRule> {
+    Some(BusRule {
+        ic_path: serde_json::from_value(rule.get("ic_path")?.clone()).ok()?,
+        src: serde_json::from_value(rule.get("src")?.clone()).ok()?,
+        sink: serde_json::from_value(rule.get("sink")?.clone()).ok()?,
+        grouped: rule.get("grouped")?.as_bool()?,
+    })
+}
+pub fn load(circuit_path: &str) -> Vec<BusRule> {
+    let path = sidecar_path(circuit_path);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let buses: Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("couldn't read {}: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+    buses
+        .get("buses")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|rule| {
+            let parsed = parse(rule);
+            if parsed.is_none() {
+                println!("skipping bad bus in {}: {}", path.display(), rule);
+            }
+            parsed
+        })
+        .collect()
+}
+pub fn save(circuit_path: &str, rules: &[BusRule]) -> Result<(), String> {
+    let buses: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "ic_path": rule.ic_path,
+                "src": rule.src,
+                "sink": rule.sink,
+                "grouped": rule.grouped,
+            })
+        })
+        .collect();
+    let path = sidecar_path(circuit_path);
+    let text = serde_json::to_string_pretty(&json!({ "buses": buses })).expect("buses to json");
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, text))
+        .map_err(|e| format!("couldn't save {}: {}", path.display(), e))
+}
+//groups wires into buses, rules are (src, sink, grouped) and win over the automatic grouping
+//automatic buses are runs of adjacent output pins wired to adjacent input pins
+pub fn find_buses(wires: &[Wire], rules: &[(usize, usize, bool)]) -> Vec<Bus> {
+    let mut pairs: Vec<(usize, usize)> = wires.iter().map(|&(src, _, sink, _)| (src, sink)).collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    let mut buses = Vec::new();
+    for (src, sink) in pairs {
+        let mut between: Vec<(usize, usize)> = wires
+            .iter()
+            .filter(|w| (w.0, w.2) == (src, sink))
+            .map(|&(_, out_pin, _, in_pin)| (out_pin, in_pin))
+            .collect();
+        between.sort_unstable();
+        match rules.iter().find(|r| (r.0, r.1) == (src, sink)).map(|r| r.2) {
+            Some(false) => {}
+            Some(true) => {
+                if between.len() > 1 {
+                    buses.push(Bus { src, sink, bits: between.iter().map(|&(_, in_pin)| in_pin).collect() });
+                }
+            }
+            None => {
+                let mut run: Vec<(usize, usize)> = Vec::new();
+                for (out_pin, in_pin) in between {
+                    let adjacent = run.last().is_some_and(|&(o, i)| o + 1 == out_pin && i + 1 == in_pin);
+                    if !adjacent {
+                        if run.len() >= MIN_AUTO_BITS {
+                            buses.push(Bus { src, sink, bits: run.iter().map(|&(_, i)| i).collect() });
+                        }
+                        run.clear();
+                    }
+                    run.push((out_pin, in_pin));
+                }
+                if run.len() >= MIN_AUTO_BITS {
+                    buses.push(Bus { src, sink, bits: run.iter().map(|&(_, i)| i).collect() });
+                }
+            }
+        }
+    }
+    buses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(buses: &[Bus]) -> Vec<Vec<usize>> {
+        buses.iter().map(|bus| bus.bits.clone()).collect()
+    }
+
+    #[test]
+    fn adjacent_pins_make_a_bus() {
+        let wires = [(0, 2, 1, 2), (0, 0, 1, 0), (0, 1, 1, 1)];
+        let buses = find_buses(&wires, &[]);
+        assert_eq!(bits(&buses), vec![vec![0, 1, 2]]);
+        assert_eq!((buses[0].src, buses[0].sink), (0, 1));
+    }
+    #[test]
+    fn gaps_split_runs() {
+        let wires = [(0, 0, 1, 0), (0, 1, 1, 1), (0, 3, 1, 3), (0, 4, 1, 4), (0, 6, 1, 6)];
+        assert_eq!(bits(&find_buses(&wires, &[])), vec![vec![0, 1], vec![3, 4]]);
+    }
+    #[test]
+    fn lone_wires_stay_wires() {
+        assert!(find_buses(&[(0, 0, 1, 0), (0, 1, 2, 1)], &[]).is_empty());
+    }
+    #[test]
+    fn rules_win() {
+        let scattered = [(0, 0, 1, 5), (0, 3, 1, 1)];
+        assert_eq!(bits(&find_buses(&scattered, &[(0, 1, true)])), vec![vec![5, 1]]);
+        let adjacent = [(0, 0, 1, 0), (0, 1, 1, 1)];
+        assert!(find_buses(&adjacent, &[(0, 1, false)]).is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use slslib::sls::{self, Circuit, NodeType, ID};
+
+//everything the palette can place besides ICs
+pub const PALETTE_TYPES: [NodeType; 13] = [
+    NodeType::AND,
+    NodeType::OR,
+    NodeType::NOT,
+    NodeType::XOR,
+    NodeType::NAND,
+    NodeType::NOR,
+    NodeType::XNOR,
+    NodeType::TOGGLE_BUTTON,
+    NodeType::PULSE_BUTTON,
+    NodeType::LIGHT_BULB,
+    NodeType::SEVEN_SEGMENT_DISPLAY,
+    NodeType::SEVEN_SEGMENT_DISPLAY_DECODER,
+    NodeType::NOTE,
+];
+pub enum PaletteItem {
+    Node(NodeType),
+    //name and path of an .slj in the library folder
+    Ic(String, PathBuf),
+}
+impl PaletteItem {
+    pub fn name(&self) -> String {
+        match self {
+            PaletteItem::Node(node_type) => node_type.to_string(),
+            PaletteItem::Ic(name, _) => name.clone(),
+        }
+    }
+}
+//every .slj in the library folders can be placed as an IC, the first folder wins when names clash
+pub fn load_palette(dirs: &[&Path]) -> Vec<PaletteItem> {
+    let mut items: Vec<PaletteItem> = PALETTE_TYPES.iter().map(|t| PaletteItem::Node(*t)).collect();
+    let mut ics: Vec<(String, PathBuf)> = Vec::new();
+    for (i, dir) in dirs.iter().enumerate() {
+        if dirs[..i].contains(dir) {
+            continue;
+        }
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            //a library in the config directory only shows up once an IC has been made
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                println!("couldn't read IC library {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            if !path.extension().is_some_and(|ext| ext == "slj") {
+                continue;
+            }
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            if !ics.iter().any(|(other, _)| *other == name) {
+                ics.push((name, path));
+            }
+        }
+    }
+    ics.sort();
+    items.extend(ics.into_iter().map(|(name, path)| PaletteItem::Ic(name, path)));
+    items
+}
+
+//hands out IDs that aren't used in a circuit yet
+//IDs are opaque to us, so this goes by how they serialize: numbers count up, anything else gets random hex
+pub struct IdGen {
+    used: Vec<Value>,
+    numeric: bool,
+    next: u64,
+}
+impl IdGen {
+    pub fn new(circuit: &Circuit) -> Self {
+        let used: Vec<Value> = circuit
+            .components
+            .iter()
+            .filter_map(|c| serde_json::to_value(c.get_id()).ok())
+            .collect();
+        let numeric = !used.is_empty() && used.iter().all(Value::is_u64);
+        let next = used.iter().filter_map(Value::as_u64).max().map_or(0, |m| m + 1);
+        IdGen { used, numeric, next }
+    }
+    pub fn next(&mut self) -> ID {
+        let id = if self.numeric {
+            self.next += 1;
+            json!(self.next - 1)
+        } else {
+            loop {
+                let mut hasher = RandomState::new().build_hasher();
+                hasher.write_usize(self.used.len());
+                let id = json!(format!("{:016x}", hasher.finish()));
+                if !self.used.contains(&id) {
+                    break id;
+                }
+            }
+        };
+        self.used.push(id.clone());
+        serde_json::from_value(id).expect("ID from json")
+    }
+}
+
+//(inputs, outputs) a fresh component of this type gets
+fn pin_counts(node_type: NodeType) -> (usize, usize) {
+    match node_type {
+        NodeType::NOT => (1, 1),
+        NodeType::TOGGLE_BUTTON | NodeType::PULSE_BUTTON => (0, 1),
+        NodeType::LIGHT_BULB => (1, 1),
+        NodeType::SEVEN_SEGMENT_DISPLAY => (7, 0),
+        NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => (4, 7),
+        NodeType::NOTE => (0, 0),
+        _ => (2, 1),
+    }
+}
+//the IC's pins are the buttons (inputs) and light bulbs (outputs) inside it, as component indexes
+pub fn ic_pin_comps(circ: &Circuit) -> (Vec<usize>, Vec<usize>) {
+    let outputs = circ
+        .components
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.node_type == NodeType::LIGHT_BULB)
+        .map(|(i, _)| i)
+        .collect();
+    (circ.inputs.clone(), outputs)
+}
+fn build(node_type: NodeType, x: f32, y: f32, id: ID, num_in: usize, num_out: usize) -> Result<sls::Component, serde_json::Error> {
+    serde_json::from_value(json!({
+        "id": id,
+        "node_type": node_type,
+        "x": x,
+        "y": y,
+        "label": null,
+        "text": if node_type == NodeType::NOTE { Some("note") } else { None },
+        "inputs": [],
+        "input_states": vec![false; num_in],
+        "outputs": vec![false; num_out],
+        "ic_instance": null,
+    }))
+}
+//the simulation only ever builds components from .slj files, so new ones go through the same path
+pub fn new_component(node_type: NodeType, x: f32, y: f32, id: ID) -> Result<sls::Component, serde_json::Error> {
+    let (num_in, num_out) = pin_counts(node_type);
+    build(node_type, x, y, id, num_in, num_out)
+}
+pub fn new_ic(mut ic: Circuit, x: f32, y: f32, id: ID) -> Result<sls::Component, serde_json::Error> {
+    ic.init_circ(None);
+    let (ins, outs) = ic_pin_comps(&ic);
+    let mut comp = build(NodeType::INTEGRATED_CIRCUIT, x, y, id, ins.len(), outs.len())?;
+    comp.label = Some(ic.header.name.clone());
+    comp.ic_instance = Some(ic.into());
+    Ok(comp)
+}
+pub fn load_ic(path: &Path) -> Result<Circuit, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("couldn't parse {}: {}", path.display(), e))
+}
+//one entry of Component::inputs, meaning in_pin is driven by other_id's other_pin
+pub fn input_json(in_pin: usize, other_pin: usize, other_id: &ID) -> Value {
+    json!({
+        "in_pin": in_pin,
+        "other_pin": other_pin,
+        "other_id": other_id,
+    })
+}
+//components as an .slj fragment, wires from anything outside of them are dropped
+pub fn copy_fragment(c: &Circuit, comps: &[usize]) -> Result<String, serde_json::Error> {
+    let ids: Vec<&ID> = comps.iter().map(|&comp_i| c.components[comp_i].get_id()).collect();
+    let mut values = Vec::with_capacity(comps.len());
+    for &comp_i in comps {
+        let mut comp = c.components[comp_i].clone();
+        comp.inputs.retain(|input| ids.contains(&&input.other_id));
+        values.push(serde_json::to_value(&comp)?);
+    }
+    serde_json::to_string_pretty(&json!({ "components": values }))
+}
+//components of a fragment (or a whole .slj) with fresh IDs, wires between them follow the new IDs
+pub fn paste_fragment(text: &str, ids: &mut IdGen) -> Result<Vec<sls::Component>, String> {
+    let mut fragment: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let comps = fragment
+        .get_mut("components")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "no components in clipboard".to_string())?;
+    //(old, new)
+    let mut remap: Vec<(Value, Value)> = Vec::with_capacity(comps.len());
+    for comp in comps.iter_mut() {
+        let new_id = serde_json::to_value(ids.next()).map_err(|e| e.to_string())?;
+        let old_id = comp
+            .get_mut("id")
+            .map(|id| std::mem::replace(id, new_id.clone()))
+            .ok_or_else(|| "component without an id".to_string())?;
+        remap.push((old_id, new_id));
+    }
+    for comp in comps.iter_mut() {
+        let Some(inputs) = comp.get_mut("inputs").and_then(Value::as_array_mut) else { continue };
+        inputs.retain_mut(|input| {
+            let Some(other_id) = input.get_mut("other_id") else { return false };
+            match remap.iter().find(|(old, _)| old == other_id) {
+                Some((_, new)) => {
+                    *other_id = new.clone();
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+    comps
+        .iter()
+        .map(|comp| serde_json::from_value(comp.clone()).map_err(|e| e.to_string()))
+        .collect()
+}
+
+//a selection turned into a circuit of its own
+pub struct Extracted {
+    pub ic: Circuit,
+    //outside (component, output pin) driving each IC input pin
+    pub inputs: Vec<(usize, usize)>,
+    //inside (component, output pin) behind each IC output pin
+    pub outputs: Vec<(usize, usize)>,
+}
+//copies comps into a new circuit, wires crossing the selection become toggle buttons (inputs) and light bulbs (outputs)
+//all indexes in the result refer to c
+pub fn extract_ic(c: &Circuit, comps: &[usize], name: &str) -> Result<Extracted, String> {
+    const PIN_GAP: f32 = 60.0;
+    const SIDE_GAP: f32 = 100.0;
+    //inside an IC those are pins, and ic_pin_comps would count them before the ones made here
+    let io = |node_type| matches!(node_type, NodeType::TOGGLE_BUTTON | NodeType::PULSE_BUTTON | NodeType::LIGHT_BULB);
+    if comps.iter().any(|&comp_i| io(c.components[comp_i].node_type)) {
+        return Err("buttons and light bulbs can't go into an IC".to_string());
+    }
+    let index_of = |id: &ID| c.components.iter().position(|comp| comp.get_id() == id);
+    let inside = |comp_i: Option<usize>| comp_i.is_some_and(|i| comps.contains(&i));
+    let mut inputs: Vec<(usize, usize)> = Vec::new();
+    for &comp_i in comps {
+        for input in &c.components[comp_i].inputs {
+            let Some(src) = index_of(&input.other_id) else { continue };
+            if !inside(Some(src)) && !inputs.contains(&(src, input.other_pin)) {
+                inputs.push((src, input.other_pin));
+            }
+        }
+    }
+    let mut outputs: Vec<(usize, usize)> = Vec::new();
+    for (comp_i, comp) in c.components.iter().enumerate() {
+        if comps.contains(&comp_i) {
+            continue;
+        }
+        for input in &comp.inputs {
+            let src = index_of(&input.other_id);
+            if inside(src) && !outputs.contains(&(src.unwrap(), input.other_pin)) {
+                outputs.push((src.unwrap(), input.other_pin));
+            }
+        }
+    }
+    let mut ic = c.clone();
+    ic.header.name = name.to_string();
+    ic.components = comps.iter().map(|&comp_i| c.components[comp_i].clone()).collect();
+    let bounds = ic.components.iter().fold(None, |acc: Option<(f32, f32, f32)>, comp| {
+        let (left, top, right) = acc.unwrap_or((comp.x, comp.y, comp.x));
+        Some((left.min(comp.x), top.min(comp.y), right.max(comp.x)))
+    });
+    let (left, top, right) = bounds.ok_or_else(|| "nothing selected".to_string())?;
+    let mut ids = IdGen::new(&ic);
+    let button_ids: Vec<ID> = inputs.iter().map(|_| ids.next()).collect();
+    for comp in &mut ic.components {
+        for input in &mut comp.inputs {
+            let Some(src) = index_of(&input.other_id) else { continue };
+            if let Some(k) = inputs.iter().position(|&i| i == (src, input.other_pin)) {
+                input.other_id = button_ids[k].clone();
+                input.other_pin = 0;
+            }
+        }
+    }
+    let mut buttons = Vec::with_capacity(inputs.len());
+    for (k, id) in button_ids.into_iter().enumerate() {
+        let button = new_component(NodeType::TOGGLE_BUTTON, left - SIDE_GAP, top + PIN_GAP * k as f32, id).map_err(|e| e.to_string())?;
+        buttons.push(ic.components.len());
+        ic.components.push(button);
+    }
+    for (k, &(src, pin)) in outputs.iter().enumerate() {
+        let mut bulb = new_component(NodeType::LIGHT_BULB, right + SIDE_GAP, top + PIN_GAP * k as f32, ids.next()).map_err(|e| e.to_string())?;
+        let input = serde_json::from_value(input_json(0, pin, c.components[src].get_id())).map_err(|e| e.to_string())?;
+        bulb.inputs.push(input);
+        ic.components.push(bulb);
+    }
+    //pin order follows the order of inputs
+    ic.inputs = buttons;
+    Ok(Extracted { ic, inputs, outputs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{circuit, comp, id};
+
+    fn ids(next: u64) -> IdGen {
+        IdGen { used: Vec::new(), numeric: true, next }
+    }
+    fn fragment(comps: &[&sls::Component]) -> String {
+        serde_json::to_string(&json!({ "components": comps })).unwrap()
+    }
+
+    #[test]
+    fn pasted_wires_follow_new_ids() {
+        let button = comp(NodeType::TOGGLE_BUTTON, 1, &[]);
+        let not = comp(NodeType::NOT, 2, &[(0, 0, 1)]);
+        let pasted = paste_fragment(&fragment(&[&button, &not]), &mut ids(100)).unwrap();
+        assert!(pasted[0].get_id() == &id(100) && pasted[1].get_id() == &id(101));
+        assert_eq!(pasted[1].inputs.len(), 1);
+        assert!(&pasted[1].inputs[0].other_id == pasted[0].get_id());
+    }
+    #[test]
+    fn wires_from_outside_are_dropped() {
+        let not = comp(NodeType::NOT, 2, &[(0, 0, 9)]);
+        let pasted = paste_fragment(&fragment(&[&not]), &mut ids(100)).unwrap();
+        assert!(pasted[0].inputs.is_empty());
+    }
+    #[test]
+    fn bad_clipboards_are_errors() {
+        assert!(paste_fragment("not json", &mut ids(0)).is_err());
+        assert!(paste_fragment("{}", &mut ids(0)).is_err());
+    }
+    //two buttons into an AND (crossed over), then a NOT, then a bulb
+    fn and_not() -> Circuit {
+        circuit(
+            "top",
+            vec![
+                comp(NodeType::TOGGLE_BUTTON, 0, &[]),
+                comp(NodeType::TOGGLE_BUTTON, 1, &[]),
+                comp(NodeType::AND, 2, &[(0, 0, 1), (1, 0, 0)]),
+                comp(NodeType::NOT, 3, &[(0, 0, 2)]),
+                comp(NodeType::LIGHT_BULB, 4, &[(0, 0, 3)]),
+            ],
+        )
+    }
+    #[test]
+    fn ic_pins_follow_the_wires_in() {
+        let extracted = extract_ic(&and_not(), &[2, 3], "gate").unwrap();
+        assert_eq!(extracted.inputs, vec![(1, 0), (0, 0)]);
+        assert_eq!(extracted.outputs, vec![(3, 0)]);
+        let ic = &extracted.ic;
+        assert_eq!(ic.header.name, "gate");
+        //AND, NOT, a button per input and a bulb per output, in pin order
+        assert_eq!(ic_pin_comps(ic), (vec![2, 3], vec![4]));
+        let and = &ic.components[0];
+        assert!(and.inputs.iter().all(|input| input.other_pin == 0));
+        assert!(and.inputs.iter().find(|input| input.in_pin == 0).unwrap().other_id == *ic.components[2].get_id());
+        assert!(and.inputs.iter().find(|input| input.in_pin == 1).unwrap().other_id == *ic.components[3].get_id());
+        //the NOT still hangs off the AND, and the bulb off the NOT
+        assert!(ic.components[1].inputs[0].other_id == id(2));
+        assert!(ic.components[4].inputs[0].other_id == id(3));
+    }
+    #[test]
+    fn buttons_and_bulbs_stay_out_of_ics() {
+        assert!(extract_ic(&and_not(), &[0, 2], "gate").is_err());
+        assert!(extract_ic(&and_not(), &[3, 4], "gate").is_err());
+        assert!(extract_ic(&and_not(), &[], "gate").is_err());
+    }
+}
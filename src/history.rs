@@ -0,0 +1,241 @@
+use slslib::sls::{self, Circuit, NodeType, ID};
+
+use crate::edit;
+
+const HISTORY_LIMIT: usize = 200;
+
+//the parts of a component the user edits, its simulation state and IC instance are left alone
+#[derive(Clone, PartialEq)]
+pub struct Fields {
+    pub x: f32,
+    pub y: f32,
+    pub label: Option<String>,
+    pub text: Option<String>,
+    //(in_pin, other_pin, other_id) of every wire in
+    pub inputs: Vec<(usize, usize, ID)>,
+    //what a toggle button starts in when saved
+    pub output: Option<bool>,
+}
+impl Fields {
+    pub fn of(comp: &sls::Component) -> Fields {
+        Fields {
+            x: comp.x,
+            y: comp.y,
+            label: comp.label.clone(),
+            text: comp.text.clone(),
+            inputs: comp.inputs.iter().map(|input| (input.in_pin, input.other_pin, input.other_id.clone())).collect(),
+            output: (comp.node_type == NodeType::TOGGLE_BUTTON).then(|| comp.outputs[0]),
+        }
+    }
+    //sets what differs between from and self, anything else the component has now stays
+    fn patch(&self, from: &Fields, comp: &mut sls::Component) {
+        if (self.x, self.y) != (from.x, from.y) {
+            comp.x = self.x;
+            comp.y = self.y;
+        }
+        if self.label != from.label {
+            comp.label = self.label.clone();
+        }
+        if self.text != from.text {
+            comp.text = self.text.clone();
+        }
+        if self.inputs != from.inputs {
+            comp.inputs = self
+                .inputs
+                .iter()
+                .map(|(in_pin, other_pin, other_id)| {
+                    serde_json::from_value(edit::input_json(*in_pin, *other_pin, other_id)).expect("input from its own fields")
+                })
+                .collect();
+            //pins nothing drives anymore read low
+            for &(in_pin, ..) in &from.inputs {
+                if !self.inputs.iter().any(|input| input.0 == in_pin) {
+                    if let Some(state) = comp.input_states.get_mut(in_pin) {
+                        *state = false;
+                    }
+                }
+            }
+        }
+        if let (Some(output), true) = (self.output, self.output != from.output) {
+            comp.outputs[0] = output;
+        }
+    }
+}
+
+//every circuit edit is one of these, so it can be undone
+pub enum ChangeKind {
+    //edited fields of the components at these indexes, (index, before, after)
+    Replace(Vec<(usize, Fields, Fields)>),
+    //components to insert, ascending indexes
+    Insert(Vec<(usize, sls::Component)>),
+    //components to remove with what they were, ascending indexes
+    Remove(Vec<(usize, sls::Component)>),
+    //applied in order, undone in reverse
+    Batch(Vec<ChangeKind>),
+}
+pub struct Change {
+    //IDs of the ICs drilled into, the edit happened in the innermost one
+    pub ic_path: Vec<ID>,
+    pub kind: ChangeKind,
+}
+impl ChangeKind {
+    fn inverse(self) -> ChangeKind {
+        match self {
+            ChangeKind::Replace(comps) => {
+                ChangeKind::Replace(comps.into_iter().map(|(i, before, after)| (i, after, before)).collect())
+            }
+            ChangeKind::Insert(comps) => ChangeKind::Remove(comps),
+            ChangeKind::Remove(comps) => ChangeKind::Insert(comps),
+            ChangeKind::Batch(changes) => ChangeKind::Batch(changes.into_iter().rev().map(ChangeKind::inverse).collect()),
+        }
+    }
+    //true if components were added or removed, which shifts indexes
+    pub fn shifts_indexes(&self) -> bool {
+        match self {
+            ChangeKind::Replace(_) => false,
+            ChangeKind::Insert(_) | ChangeKind::Remove(_) => true,
+            ChangeKind::Batch(changes) => changes.iter().any(ChangeKind::shifts_indexes),
+        }
+    }
+    //true if the simulation has to be re-initialized, moves and relabels don't
+    pub fn changes_wiring(&self) -> bool {
+        match self {
+            ChangeKind::Replace(comps) => comps.iter().any(|(_, before, after)| before.inputs != after.inputs),
+            ChangeKind::Insert(_) | ChangeKind::Remove(_) => true,
+            ChangeKind::Batch(changes) => changes.iter().any(ChangeKind::changes_wiring),
+        }
+    }
+    pub fn apply(&self, c: &mut Circuit) {
+        match self {
+            ChangeKind::Replace(comps) => {
+                for (i, before, after) in comps {
+                    after.patch(before, &mut c.components[*i]);
+                }
+                //a toggle's state changed under the simulation
+                if comps.iter().any(|(_, before, after)| before.output != after.output) {
+                    c.comps_changed = true;
+                }
+            }
+            ChangeKind::Insert(comps) => {
+                for (i, comp) in comps {
+                    c.components.insert(*i, comp.clone());
+                }
+            }
+            ChangeKind::Remove(comps) => {
+                for (i, _) in comps.iter().rev() {
+                    c.components.remove(*i);
+                }
+            }
+            ChangeKind::Batch(changes) => {
+                for change in changes {
+                    change.apply(c);
+                }
+            }
+        }
+    }
+}
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+impl History {
+    //records a change that's already been applied
+    pub fn push(&mut self, change: Change) {
+        self.redo.clear();
+        self.undo.push(change);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+    //where the next undo or redo happens, checked before either moves anything between the stacks
+    pub fn undo_ic_path(&self) -> Option<&[ID]> {
+        self.undo.last().map(|change| change.ic_path.as_slice())
+    }
+    pub fn redo_ic_path(&self) -> Option<&[ID]> {
+        self.redo.last().map(|change| change.ic_path.as_slice())
+    }
+    //the change that undoes the last one, to be applied by the caller
+    pub fn undo(&mut self) -> Option<&Change> {
+        let change = self.undo.pop()?;
+        self.redo.push(Change { ic_path: change.ic_path, kind: change.kind.inverse() });
+        self.redo.last()
+    }
+    pub fn redo(&mut self) -> Option<&Change> {
+        let change = self.redo.pop()?;
+        self.undo.push(Change { ic_path: change.ic_path, kind: change.kind.inverse() });
+        self.undo.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{comp, id};
+
+    #[test]
+    fn undoing_a_move_keeps_the_simulation_state() {
+        let mut comp = comp(NodeType::TOGGLE_BUTTON, 0, &[]);
+        let before = Fields::of(&comp);
+        let mut after = before.clone();
+        after.x = 40.0;
+        after.patch(&before, &mut comp);
+        //flipped while playing, after the move
+        comp.outputs[0] = true;
+        before.patch(&after, &mut comp);
+        assert_eq!((comp.x, comp.y), (0.0, 0.0));
+        assert!(comp.outputs[0]);
+    }
+    #[test]
+    fn toggling_the_initial_state() {
+        let mut comp = comp(NodeType::TOGGLE_BUTTON, 0, &[]);
+        let before = Fields::of(&comp);
+        let mut after = before.clone();
+        after.output = Some(true);
+        after.patch(&before, &mut comp);
+        assert!(comp.outputs[0]);
+        before.patch(&after, &mut comp);
+        assert!(!comp.outputs[0]);
+    }
+    #[test]
+    fn unwired_pins_read_low() {
+        let mut comp = comp(NodeType::AND, 1, &[(0, 0, 0)]);
+        comp.input_states[0] = true;
+        let before = Fields::of(&comp);
+        let mut after = before.clone();
+        after.inputs.clear();
+        after.patch(&before, &mut comp);
+        assert!(comp.inputs.is_empty());
+        assert!(!comp.input_states[0]);
+        before.patch(&after, &mut comp);
+        assert_eq!(Fields::of(&comp).inputs, vec![(0, 0, id(0))]);
+    }
+    #[test]
+    fn batches_undo_in_reverse() {
+        let comp = comp(NodeType::NOT, 0, &[]);
+        let change = ChangeKind::Batch(vec![ChangeKind::Remove(vec![(0, comp.clone())]), ChangeKind::Insert(vec![(3, comp)])]);
+        assert!(change.shifts_indexes() && change.changes_wiring());
+        let ChangeKind::Batch(inverse) = change.inverse() else { panic!("not a batch") };
+        assert!(matches!(inverse.as_slice(), [ChangeKind::Remove(r), ChangeKind::Insert(i)] if r[0].0 == 3 && i[0].0 == 0));
+    }
+    #[test]
+    fn peeking_leaves_the_stacks_alone() {
+        let mut history = History::default();
+        history.push(Change { ic_path: vec![id(7)], kind: ChangeKind::Insert(vec![(0, comp(NodeType::NOT, 0, &[]))]) });
+        assert_eq!(history.undo_ic_path(), Some(&[id(7)][..]));
+        assert_eq!(history.undo_ic_path(), Some(&[id(7)][..]));
+        assert!(history.redo_ic_path().is_none());
+        assert!(matches!(history.undo().map(|change| &change.kind), Some(ChangeKind::Remove(_))));
+        assert!(history.undo_ic_path().is_none());
+        assert_eq!(history.redo_ic_path(), Some(&[id(7)][..]));
+    }
+    #[test]
+    fn moves_dont_rewire() {
+        let comp = comp(NodeType::NOT, 0, &[]);
+        let before = Fields::of(&comp);
+        let mut after = before.clone();
+        after.y = 10.0;
+        let change = ChangeKind::Replace(vec![(0, before, after)]);
+        assert!(!change.shifts_indexes() && !change.changes_wiring());
+    }
+}
@@ -3,7 +3,19 @@
 #[macro_use]
 extern crate lazy_static;
 use std::mem::MaybeUninit;
+mod arrange;
+mod bookmarks;
+mod buses;
+mod edit;
+mod history;
+mod route;
+mod shapes;
 mod state;
+mod svg;
+#[cfg(test)]
+mod test_util;
+mod theme;
+mod verilog;
 use raylib::prelude::*;
 pub(crate) use state::State;
 
@@ -57,10 +69,79 @@ fn draw_loop(state:&mut State) {
     }
 }
 
+const USAGE: &str = "usage: raylib_sls [circuit.slj]
+       raylib_sls --svg out.svg [--states] [--straight] [--ieee] [--theme light|dark|high-contrast] [circuit.slj]
+       raylib_sls --verilog out.v [circuit.slj]";
+enum Cli {
+    //open the viewer on this circuit, the built in one if None
+    View(Option<String>),
+    //exports run without opening a window
+    Export(Result<String, String>),
+}
+fn run_cli(args: &[String]) -> Cli {
+    let mut args = args.iter();
+    let mut svg_path = None;
+    let mut verilog_path = None;
+    let mut circuit_path = None;
+    //notes come out the size they have in the viewer
+    let mut options = svg::SvgOptions { note_size: theme::load_note_size().unwrap_or(state::DEFAULT_NOTE_SIZE), ..Default::default() };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--svg" => match args.next() {
+                Some(path) => svg_path = Some(path.clone()),
+                None => return Cli::Export(Err(USAGE.to_string())),
+            },
+            "--verilog" => match args.next() {
+                Some(path) => verilog_path = Some(path.clone()),
+                None => return Cli::Export(Err(USAGE.to_string())),
+            },
+            "--states" => options.states = true,
+            "--straight" => options.orthogonal_wires = false,
+            "--ieee" => options.gate_style = shapes::GateStyle::Ieee,
+            "--theme" => {
+                let name = args.next().map(|n| n.replace('-', " "));
+                match name.as_deref().and_then(theme::ThemeKind::from_name) {
+                    Some(kind) => options.theme = kind,
+                    None => return Cli::Export(Err(USAGE.to_string())),
+                }
+            }
+            _ if arg.starts_with("--") => return Cli::Export(Err(USAGE.to_string())),
+            _ => circuit_path = Some(arg.clone()),
+        }
+    }
+    let source = circuit_path.clone().unwrap_or_else(|| state::CIRCUIT_PATH.to_string());
+    match (svg_path, verilog_path) {
+        (None, None) => Cli::View(circuit_path),
+        (Some(svg_path), None) => Cli::Export(svg::export_file(&source, &svg_path, &options).map(|_| format!("wrote {}", svg_path))),
+        (None, Some(verilog_path)) => Cli::Export(verilog::export_file(&source, &verilog_path).map(|_| format!("wrote {}", verilog_path))),
+        (Some(_), Some(_)) => Cli::Export(Err(USAGE.to_string())),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let circuit_path = match run_cli(&args) {
+        Cli::View(circuit_path) => circuit_path,
+        Cli::Export(Ok(msg)) => {
+            println!("{}", msg);
+            return;
+        }
+        Cli::Export(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let circuit = match circuit_path.as_deref().map(|path| edit::load_ic(std::path::Path::new(path))) {
+        None => state::builtin_circuit(),
+        Some(Ok(circuit)) => circuit,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     println!("Hewroo world :3!");
     std::env::set_var("RUST_BACKTRACE", "full");
-    unsafe {&mut STATE}.write(State::new());
+    unsafe {&mut STATE}.write(State::new(circuit, circuit_path));
     //static s:std::cell::LazyCell<State> = std::cell::LazyCell::new(||State::new());
     // let mut s:LazyCell<State> = LazyCell::new(||State::new());
     #[cfg(target_family = "wasm")]
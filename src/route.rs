@@ -0,0 +1,97 @@
+use raylib::math::{Rectangle, Vector2};
+
+//how far a wire leaves a pin before it may turn
+const STUB: f32 = 10.0;
+//how far from the middle (or from the components) bends get tried
+const STEP: f32 = 10.0;
+const SEARCH_STEPS: usize = 24;
+
+//does the axis-aligned segment a-b cross r
+fn crosses(a: Vector2, b: Vector2, r: &Rectangle) -> bool {
+    a.x.min(b.x) < r.x + r.width && a.x.max(b.x) > r.x && a.y.min(b.y) < r.y + r.height && a.y.max(b.y) > r.y
+}
+fn is_clear(points: &[Vector2], obstacles: &[Rectangle]) -> bool {
+    points.windows(2).all(|seg| obstacles.iter().all(|r| !crosses(seg[0], seg[1], r)))
+}
+//only what's near the wire can get in its way, the two components it connects never do
+pub fn obstacles_near(from: Vector2, to: Vector2, rects: &[Rectangle], ends: [usize; 2]) -> Vec<Rectangle> {
+    let reach = STEP * SEARCH_STEPS as f32 + STUB;
+    let area = Rectangle::new(
+        from.x.min(to.x) - reach,
+        from.y.min(to.y) - reach,
+        (from.x - to.x).abs() + reach * 2.0,
+        (from.y - to.y).abs() + reach * 2.0,
+    );
+    rects
+        .iter()
+        .enumerate()
+        .filter(|(i, r)| !ends.contains(i) && r.check_collision_recs(&area))
+        .map(|(_, r)| *r)
+        .collect()
+}
+//horizontal and vertical segments from an output pin to an input pin, going around obstacles if it can
+pub fn route(from: Vector2, to: Vector2, obstacles: &[Rectangle]) -> Vec<Vector2> {
+    let a = Vector2::new(from.x + STUB, from.y);
+    let b = Vector2::new(to.x - STUB, to.y);
+    let mid = (a.x + b.x) / 2.0;
+    let through = |x: f32| vec![from, Vector2::new(x, from.y), Vector2::new(x, to.y), to];
+    if a.x <= b.x {
+        //one vertical run, as close to the middle as it can be
+        for i in 0..SEARCH_STEPS {
+            for x in [mid + i as f32 * STEP, mid - i as f32 * STEP] {
+                if x < a.x || x > b.x {
+                    continue;
+                }
+                let path = through(x);
+                if is_clear(&path, obstacles) {
+                    return path;
+                }
+            }
+        }
+    }
+    //backwards (feedback) or blocked: go over or under through a horizontal channel
+    let top = from.y.min(to.y);
+    let bottom = from.y.max(to.y);
+    for i in 1..=SEARCH_STEPS {
+        for y in [bottom + i as f32 * STEP, top - i as f32 * STEP] {
+            let path = vec![from, a, Vector2::new(a.x, y), Vector2::new(b.x, y), b, to];
+            if is_clear(&path, obstacles) {
+                return path;
+            }
+        }
+    }
+    through(mid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_wires_bend_in_the_middle() {
+        let path = route(Vector2::new(0.0, 0.0), Vector2::new(100.0, 40.0), &[]);
+        assert_eq!(path, vec![Vector2::new(0.0, 0.0), Vector2::new(50.0, 0.0), Vector2::new(50.0, 40.0), Vector2::new(100.0, 40.0)]);
+    }
+    #[test]
+    fn backwards_wires_loop_around() {
+        let path = route(Vector2::new(100.0, 0.0), Vector2::new(0.0, 0.0), &[]);
+        assert_eq!(path.len(), 6);
+        assert_eq!(path[2], Vector2::new(100.0 + STUB, STEP));
+        assert_eq!(path[3], Vector2::new(-STUB, STEP));
+    }
+    #[test]
+    fn blocked_wires_go_over_or_under() {
+        //a wall across every vertical run between the pins
+        let wall = [Rectangle::new(40.0, -100.0, 20.0, 200.0)];
+        let path = route(Vector2::new(0.0, 0.0), Vector2::new(100.0, 40.0), &wall);
+        assert_eq!(path.len(), 6);
+        assert!(is_clear(&path, &wall));
+    }
+    #[test]
+    fn nothing_clear_falls_back_to_the_middle() {
+        let everywhere = [Rectangle::new(-1000.0, -1000.0, 2000.0, 2000.0)];
+        let path = route(Vector2::new(0.0, 0.0), Vector2::new(100.0, 40.0), &everywhere);
+        assert_eq!(path[1], Vector2::new(50.0, 0.0));
+        assert_eq!(path.len(), 4);
+    }
+}
@@ -0,0 +1,220 @@
+use std::f32::consts::PI;
+
+use raylib::prelude::*;
+use slslib::sls::NodeType;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GateStyle {
+    //rectangles with the IEC symbol inside
+    Box,
+    //distinctive shapes
+    Ieee,
+}
+pub const BUBBLE_RADIUS: f32 = 4.0;
+const CURVE_STEPS: usize = 12;
+const OUTLINE_THICKNES: f32 = 2.0;
+pub const SYMBOL_SIZE: i32 = 16;
+pub const DECODER_PIN_SIZE: i32 = 10;
+pub const DECODER_INPUTS: [&str; 4] = ["1", "2", "4", "8"];
+pub const DECODER_OUTPUTS: [&str; 7] = ["a", "b", "c", "d", "e", "f", "g"];
+
+pub fn is_gate(node_type: NodeType) -> bool {
+    matches!(
+        node_type,
+        NodeType::AND | NodeType::OR | NodeType::NOT | NodeType::XOR | NodeType::NAND | NodeType::NOR | NodeType::XNOR
+    )
+}
+pub fn inverted(node_type: NodeType) -> bool {
+    matches!(node_type, NodeType::NOT | NodeType::NAND | NodeType::NOR | NodeType::XNOR)
+}
+pub fn symbol(node_type: NodeType) -> &'static str {
+    match node_type {
+        NodeType::AND | NodeType::NAND => "&",
+        NodeType::OR | NodeType::NOR => ">=1",
+        NodeType::XOR | NodeType::XNOR => "=1",
+        _ => "1",
+    }
+}
+fn quad(p0: Vector2, p1: Vector2, p2: Vector2, t: f32) -> Vector2 {
+    p0.scale_by((1.0 - t) * (1.0 - t)) + p1.scale_by(2.0 * (1.0 - t) * t) + p2.scale_by(t * t)
+}
+//back edge of OR-like gates, bottom to top, bulging into the body
+fn or_back(x: f32, y: f32, w: f32, h: f32) -> Vec<Vector2> {
+    (0..=CURVE_STEPS)
+        .map(|i| {
+            let t = i as f32 / CURVE_STEPS as f32;
+            Vector2::new(x + w * 0.25 * (PI * t).sin(), y + h - h * t)
+        })
+        .collect()
+}
+//outline of a gate body inside r, every point can be seen from the second value
+fn body(node_type: NodeType, r: Rectangle) -> (Vec<Vector2>, Vector2) {
+    let (x, y, w, h) = (r.x, r.y, r.width, r.height);
+    let mid = y + h / 2.0;
+    match node_type {
+        NodeType::AND | NodeType::NAND => {
+            let (rx, ry) = (w / 2.0, h / 2.0);
+            let mut points = vec![Vector2::new(x, y)];
+            points.extend((0..=CURVE_STEPS).map(|i| {
+                let angle = -PI / 2.0 + PI * i as f32 / CURVE_STEPS as f32;
+                Vector2::new(x + rx + rx * angle.cos(), mid + ry * angle.sin())
+            }));
+            points.push(Vector2::new(x, y + h));
+            (points, Vector2::new(x + rx, mid))
+        }
+        NodeType::OR | NodeType::NOR | NodeType::XOR | NodeType::XNOR => {
+            let tip = Vector2::new(x + w, mid);
+            let top = (0..=CURVE_STEPS)
+                .map(|i| quad(Vector2::new(x, y), Vector2::new(x + w * 0.6, y), tip, i as f32 / CURVE_STEPS as f32));
+            let bottom = (1..=CURVE_STEPS).map(|i| {
+                quad(tip, Vector2::new(x + w * 0.6, y + h), Vector2::new(x, y + h), i as f32 / CURVE_STEPS as f32)
+            });
+            let mut points: Vec<Vector2> = top.chain(bottom).collect();
+            let back = or_back(x, y, w, h);
+            points.extend(&back[1..back.len() - 1]);
+            (points, Vector2::new(x + w * 0.55, mid))
+        }
+        _ => (
+            vec![Vector2::new(x, y), Vector2::new(x + w, mid), Vector2::new(x, y + h)],
+            Vector2::new(x + w / 3.0, mid),
+        ),
+    }
+}
+//raylib wants triangles counter-clockwise on screen
+fn fill_fan(d: &mut impl RaylibDraw, center: Vector2, points: &[Vector2], color: Color) {
+    for (i, &a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let cross = (a.x - center.x) * (b.y - center.y) - (a.y - center.y) * (b.x - center.x);
+        if cross < 0.0 {
+            d.draw_triangle(center, a, b, color);
+        } else {
+            d.draw_triangle(center, b, a, color);
+        }
+    }
+}
+fn outline(d: &mut impl RaylibDraw, points: &[Vector2], closed: bool, color: Color) {
+    for seg in points.windows(2) {
+        d.draw_line_ex(seg[0], seg[1], OUTLINE_THICKNES, color);
+    }
+    if closed {
+        d.draw_line_ex(points[points.len() - 1], points[0], OUTLINE_THICKNES, color);
+    }
+}
+//where the parts of a gate filling rect go, the output side ends at the right edge
+pub struct GateLayout {
+    //the box, or the shape's outline with a point that sees all of it
+    pub body: Rectangle,
+    pub outline: Vec<Vector2>,
+    pub center: Vector2,
+    //XOR's extra back line
+    pub back: Option<Vec<Vector2>>,
+    pub bubble: Option<Vector2>,
+}
+pub fn gate_layout(node_type: NodeType, rect: Rectangle, style: GateStyle) -> GateLayout {
+    let mut body_rect = rect;
+    let bubble = inverted(node_type).then(|| {
+        body_rect.width -= BUBBLE_RADIUS * 2.0;
+        Vector2::new(rect.x + rect.width - BUBBLE_RADIUS, rect.y + rect.height / 2.0)
+    });
+    let mut back = None;
+    //XOR's back line sits in front of the body
+    if style == GateStyle::Ieee && matches!(node_type, NodeType::XOR | NodeType::XNOR) {
+        let gap = body_rect.width * 0.15;
+        back = Some(or_back(body_rect.x, body_rect.y, body_rect.width, body_rect.height));
+        body_rect.x += gap;
+        body_rect.width -= gap;
+    }
+    let (outline, center) = match style {
+        GateStyle::Box => (Vec::new(), Vector2::new(body_rect.x + body_rect.width / 2.0, body_rect.y + body_rect.height / 2.0)),
+        GateStyle::Ieee => body(node_type, body_rect),
+    };
+    GateLayout { body: body_rect, outline, center, back, bubble }
+}
+//draws a logic gate filling rect
+pub fn draw_gate(d: &mut impl RaylibDraw, node_type: NodeType, rect: Rectangle, style: GateStyle, fill: Color, line: Color) {
+    let layout = gate_layout(node_type, rect, style);
+    match style {
+        GateStyle::Box => {
+            d.draw_rectangle_rec(layout.body, fill);
+            d.draw_rectangle_lines_ex(layout.body, OUTLINE_THICKNES, line);
+            let text = symbol(node_type);
+            let width = measure_text(text, SYMBOL_SIZE) as f32;
+            let x = layout.center.x - width / 2.0;
+            let y = layout.center.y - SYMBOL_SIZE as f32 / 2.0;
+            d.draw_text(text, x as i32, y as i32, SYMBOL_SIZE, line);
+        }
+        GateStyle::Ieee => {
+            if let Some(back) = &layout.back {
+                outline(d, back, false, line);
+            }
+            fill_fan(d, layout.center, &layout.outline, fill);
+            outline(d, &layout.outline, true, line);
+        }
+    }
+    if let Some(center) = layout.bubble {
+        d.draw_circle_v(center, BUBBLE_RADIUS, fill);
+        d.draw_circle_lines(center.x as i32, center.y as i32, BUBBLE_RADIUS, line);
+    }
+}
+//BCD to seven-segment decoder, a box with its pins named
+pub fn draw_decoder(d: &mut impl RaylibDraw, rect: Rectangle, in_pins: &[Vector2], out_pins: &[Vector2], fill: Color, line: Color) {
+    d.draw_rectangle_rec(rect, fill);
+    d.draw_rectangle_lines_ex(rect, OUTLINE_THICKNES, line);
+    let half = DECODER_PIN_SIZE / 2;
+    for (pin, name) in in_pins.iter().zip(DECODER_INPUTS) {
+        d.draw_text(name, (rect.x + 3.0) as i32, pin.y as i32 - half, DECODER_PIN_SIZE, line);
+    }
+    for (pin, name) in out_pins.iter().zip(DECODER_OUTPUTS) {
+        let width = measure_text(name, DECODER_PIN_SIZE);
+        d.draw_text(name, (rect.x + rect.width - 3.0) as i32 - width, pin.y as i32 - half, DECODER_PIN_SIZE, line);
+    }
+    let title = "7seg";
+    let width = measure_text(title, DECODER_PIN_SIZE) as f32;
+    d.draw_text(title, (rect.x + (rect.width - width) / 2.0) as i32, (rect.y + 2.0) as i32, DECODER_PIN_SIZE, line);
+}
+//a segment from a to b with pointed ends, shortened so neighbours don't touch
+fn segment(a: Vector2, b: Vector2, thickness: f32) -> Vec<Vector2> {
+    let len = a.distance_to(b);
+    let dir = (b - a).scale_by(1.0 / len);
+    let normal = Vector2::new(-dir.y, dir.x).scale_by(thickness / 2.0);
+    let gap = dir.scale_by(thickness * 0.15);
+    let (a, b) = (a + gap, b - gap);
+    let bevel = dir.scale_by(thickness / 2.0);
+    vec![a, a + bevel + normal, b - bevel + normal, b, b - bevel - normal, a + bevel - normal]
+}
+//segments a-g as outlines, and the decimal point as (center, radius)
+pub fn seven_segment_layout(rect: Rectangle) -> (Vec<Vec<Vector2>>, (Vector2, f32)) {
+    let thickness = rect.width.min(rect.height) * 0.12;
+    let pad = thickness;
+    //room for the decimal point on the right
+    let left = rect.x + pad + thickness / 2.0;
+    let right = rect.x + rect.width - pad - thickness * 2.0;
+    let top = rect.y + pad + thickness / 2.0;
+    let bottom = rect.y + rect.height - pad - thickness / 2.0;
+    let mid = (top + bottom) / 2.0;
+    let corners = [
+        (Vector2::new(left, top), Vector2::new(right, top)),
+        (Vector2::new(right, top), Vector2::new(right, mid)),
+        (Vector2::new(right, mid), Vector2::new(right, bottom)),
+        (Vector2::new(left, bottom), Vector2::new(right, bottom)),
+        (Vector2::new(left, mid), Vector2::new(left, bottom)),
+        (Vector2::new(left, top), Vector2::new(left, mid)),
+        (Vector2::new(left, mid), Vector2::new(right, mid)),
+    ];
+    let segments = corners.into_iter().map(|(a, b)| segment(a, b, thickness)).collect();
+    (segments, (Vector2::new(right + thickness * 1.2, bottom), thickness * 0.6))
+}
+//seven segments a-g in input order, then the decimal point if there is an 8th input
+pub fn draw_seven_segment(d: &mut impl RaylibDraw, rect: Rectangle, states: &[bool], background: Color, on: Color, off: Color) {
+    d.draw_rectangle_rec(rect, background);
+    let (segments, (dp, dp_radius)) = seven_segment_layout(rect);
+    for (i, points) in segments.iter().enumerate() {
+        let color = if states.get(i).copied().unwrap_or(false) { on } else { off };
+        let center = (points[0] + points[3]).scale_by(0.5);
+        fill_fan(d, center, points, color);
+    }
+    if states.len() >= 8 {
+        let color = if states[7] { on } else { off };
+        d.draw_circle_v(dp, dp_radius, color);
+    }
+}
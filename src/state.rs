@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
@@ -10,6 +11,15 @@ use raylib::core::math::Vector2;
 use raylib::{camera::Camera2D, color::Color, ffi::Gesture, RaylibHandle};
 use slslib::sls::{self, Circuit, NodeType, ID};
 
+use crate::arrange::{arrange, Arrange};
+use crate::bookmarks::{self, Bookmark, Bookmarks};
+use crate::buses::{self, Bus, BusRule};
+use crate::edit::{self, IdGen, PaletteItem};
+use crate::history::{Change, ChangeKind, Fields, History};
+use crate::route;
+use crate::shapes::{self, GateStyle};
+use crate::theme::{self, Theme, ThemeKind};
+
 fn max<T: PartialOrd>(n1: T, n2: T) -> T {
     std::cmp::max_by(n1, n2, |a, b| {
         a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
@@ -21,12 +31,152 @@ enum ZoomStyle {
 }
 struct Settings {
     zoom_style: ZoomStyle,
+    note_size: i32,
+    show_minimap: bool,
+    show_inspector: bool,
+    show_grid: bool,
+    edit_mode: bool,
+    snap_to_grid: bool,
+    //bend wires around components instead of drawing them straight
+    orthogonal_wires: bool,
+    gate_style: GateStyle,
+    theme: ThemeKind,
 }
 //all indexes
 struct CompInput {
     in_pin: usize,
     other_pin: usize,
     other_comp: usize,
+    //points the wire goes through, from the output pin to the input pin
+    route: Vec<Vector2>,
+    //index into State::buses if the wire is part of one
+    bus: Option<usize>,
+}
+//reverse of CompInput, all indexes
+struct CompOutput {
+    out_pin: usize,
+    other_pin: usize,
+    other_comp: usize,
+}
+//a net is named by the output pin that drives it
+#[derive(Clone, Copy, PartialEq)]
+struct Net {
+    comp: usize,
+    pin: usize,
+}
+struct Search {
+    query: String,
+    //component indexes
+    hits: Vec<usize>,
+    selected: usize,
+}
+//eases the camera from one view to another
+struct CamAnim {
+    from_target: Vector2,
+    from_zoom: f32,
+    to_target: Vector2,
+    to_zoom: f32,
+    start: Instant,
+}
+//components being dragged in edit mode, the one grabbed is the anchor
+struct Moving {
+    anchor: usize,
+    //from the pointer to the anchor's corner, in world units
+    grab_offset: Vector2,
+    //everything that moves along, as it was before the drag
+    before: Vec<(usize, Fields)>,
+}
+#[derive(Clone, Copy, PartialEq)]
+enum TextTarget {
+    Label,
+    Note,
+    //position fields in the inspector
+    X,
+    Y,
+}
+//what clicking an inspector row does
+#[derive(Clone, Copy)]
+enum InspectorAction {
+    Edit(TextTarget),
+    ToggleState,
+    EnterIc,
+}
+//a label or note being typed into
+struct TextEdit {
+    comp: usize,
+    target: TextTarget,
+    text: String,
+    //in chars
+    cursor: usize,
+}
+impl TextEdit {
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map_or(self.text.len(), |(i, _)| i)
+    }
+    fn insert(&mut self, c: char) {
+        let i = self.byte_index(self.cursor);
+        self.text.insert(i, c);
+        self.cursor += 1;
+    }
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let i = self.byte_index(self.cursor);
+            self.text.remove(i);
+        }
+    }
+    fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let i = self.byte_index(self.cursor);
+            self.text.remove(i);
+        }
+    }
+    //start of the line the cursor is on, and the text before the cursor on that line
+    fn cursor_line(&self) -> (usize, &str) {
+        let before = &self.text[..self.byte_index(self.cursor)];
+        let line = before.matches('\n').count();
+        (line, before.rsplit('\n').next().unwrap_or(""))
+    }
+}
+//maps the whole circuit into a corner of the screen
+struct MinimapView {
+    screen: Rectangle,
+    world: Rectangle,
+    scale: f32,
+}
+impl MinimapView {
+    fn to_screen(&self, world_pos: Vector2) -> Vector2 {
+        self.screen_origin() + (world_pos - Vector2::new(self.world.x, self.world.y)).scale_by(self.scale)
+    }
+    fn to_world(&self, screen_pos: Vector2) -> Vector2 {
+        Vector2::new(self.world.x, self.world.y) + (screen_pos - self.screen_origin()).scale_by(1.0 / self.scale)
+    }
+    fn rect_to_screen(&self, rect: Rectangle) -> Rectangle {
+        let tl = self.to_screen(Vector2::new(rect.x, rect.y));
+        Rectangle::new(tl.x, tl.y, rect.width * self.scale, rect.height * self.scale)
+    }
+    //the circuit is centered inside the minimap
+    fn screen_origin(&self) -> Vector2 {
+        Vector2::new(
+            self.screen.x + (self.screen.width - self.world.width * self.scale) / 2.0,
+            self.screen.y + (self.screen.height - self.world.height * self.scale) / 2.0,
+        )
+    }
+}
+//an image to render at the start of the next draw
+struct PngExport {
+    //world rect to capture
+    area: Rectangle,
+    //pixels per world unit
+    zoom: f32,
+    path: PathBuf,
+}
+//what's under the pointer (all indexes)
+#[derive(Clone, Copy, PartialEq)]
+enum Hover {
+    Comp(usize),
+    InPin(usize, usize),
+    OutPin(usize, usize),
 }
 pub struct State {
     pub rl: raylib::core::RaylibHandle,
@@ -39,6 +189,9 @@ pub struct State {
     in_pin_pos: Vec<Vec<Vector2>>,
     out_pin_pos: Vec<Vec<Vector2>>,
     comp_inputs: Vec<Vec<CompInput>>,
+    comp_outputs: Vec<Vec<CompOutput>>,
+    //(input, output) pin names of each IC, from the labels of the buttons and bulbs inside
+    ic_pin_names: Vec<(Vec<String>, Vec<String>)>,
     drag_start:Option<Vector2>,
     initial_distance: f32,
     initial_zoom: f32,
@@ -47,18 +200,123 @@ pub struct State {
     tick_rate:f64,
     begin:Instant,
     run_times: usize,
+    hover: Option<Hover>,
+    highlight: Option<Net>,
+    search: Option<Search>,
+    cam_anim: Option<CamAnim>,
+    //component indexes
+    selected: Vec<usize>,
+    pointer_on_minimap: bool,
+    //component indexes of the ICs drilled into, outermost first
+    ic_path: Vec<usize>,
+    bookmarks: Bookmarks,
+    //slot being saved and its name so far
+    naming: Option<(usize, String)>,
+    moving: Option<Moving>,
+    palette: Vec<PaletteItem>,
+    palette_scroll: f32,
+    //palette index being dragged onto the canvas
+    placing: Option<usize>,
+    //output pin a new wire is being dragged from
+    wiring: Option<Net>,
+    //(component, input pin) of the wire picked in edit mode
+    selected_wire: Option<(usize, usize)>,
+    status: Option<(String, Instant)>,
+    history: History,
+    //world position a selection box was started at
+    box_select: Option<Vector2>,
+    //used when the system clipboard isn't available
+    clipboard: Option<String>,
+    text_edit: Option<TextEdit>,
+    buses: Vec<Bus>,
+    bus_rules: Vec<BusRule>,
+    //(src, sink) of the buses drawn as their separate wires
+    expanded_buses: Vec<(usize, usize)>,
+    //whole circuit (or just the view) and the scale typed so far
+    export_prompt: Option<(bool, String)>,
+    pending_export: Option<PngExport>,
+    //the .slj being edited, None for the built in circuit until it's saved somewhere
+    circuit_path: Option<String>,
+    //path typed so far for Ctrl+S when there's no circuit_path
+    save_prompt: Option<String>,
+}
+//where the built in circuit comes from, it's only ever read
+pub(crate) const CIRCUIT_PATH: &str = "sls/prog-proc-8-bit.slj";
+pub fn builtin_circuit() -> sls::Circuit {
+    serde_json::from_str(include_str!("../sls/prog-proc-8-bit.slj")).expect("the built in circuit parses")
+}
+//sidecar files hang off the circuit being edited, or a stand-in for the built in one in the config directory
+fn data_path(circuit_path: Option<&str>) -> String {
+    match circuit_path {
+        Some(path) => path.to_string(),
+        None => {
+            let name = Path::new(CIRCUIT_PATH).file_name().unwrap_or_default();
+            theme::config_dir().join(name).to_string_lossy().into_owned()
+        }
+    }
 }
-const BUTTON_SIZE: f32 = 50.0;
-const COMP_SIZE: f32 = 50.0;
-const MIN_IC_COMP_SIZE: f32 = 52.0;
+//ICs made from a circuit go next to it, a bare file name means the working directory
+fn library_dir(circuit_path: &str) -> &Path {
+    Path::new(circuit_path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."))
+}
+const SLOT_KEYS: [KeyboardKey; bookmarks::SLOTS] = [
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE,
+    KeyboardKey::KEY_SIX,
+    KeyboardKey::KEY_SEVEN,
+    KeyboardKey::KEY_EIGHT,
+    KeyboardKey::KEY_NINE,
+];
+pub(crate) const BUTTON_SIZE: f32 = 50.0;
+pub(crate) const BUTTON_BORDER: f32 = 5.0;
+pub(crate) const COMP_SIZE: f32 = 50.0;
+pub(crate) const MIN_IC_COMP_SIZE: f32 = 52.0;
 const MIN_OUTER_PADDING: f32 = PIN_SIZE + 5.0;
 const PIN_SPACING: f32 = (PIN_SIZE * 2.0) + 2.0;
-const PIN_SIZE: f32 = 5.0;
-const PIN_COLOR: Color = Color::GRAY;
-const PIN_LEN: f32 = PIN_SIZE + 2.0;
-const ON_COLOR: Color = Color::GREEN;
-const OFF_COLOR: Color = Color::BLACK;
-const WIRE_THICKNES: f32 = 2.0;
+pub(crate) const PIN_SIZE: f32 = 5.0;
+pub(crate) const PIN_LEN: f32 = PIN_SIZE + 2.0;
+pub(crate) const WIRE_THICKNES: f32 = 2.0;
+const HIGHLIGHT_THICKNES: f32 = 4.0;
+const BUS_THICKNES: f32 = 6.0;
+const BUS_LABEL_SIZE: i32 = 10;
+const PIN_NAME_SIZE: i32 = 8;
+//smallest on-screen text height pin names are drawn at
+const MIN_PIN_NAME_PIXELS: f32 = 5.0;
+//characters a pin name keeps at zoom 1, fewer when zoomed out
+const PIN_NAME_CHARS: f32 = 8.0;
+pub(crate) const LABEL_SIZE: i32 = 12;
+pub(crate) const DEFAULT_NOTE_SIZE: i32 = 40;
+const MIN_NOTE_SIZE: i32 = 8;
+const TOOLTIP_SIZE: i32 = 12;
+const SEARCH_SIZE: i32 = 16;
+const SEARCH_MAX_SHOWN: usize = 10;
+const CAM_ANIM_SECS: f32 = 0.4;
+const FIT_FILL: f32 = 0.9;
+const MINIMAP_FRACTION: f32 = 0.25;
+const MINIMAP_MARGIN: f32 = 10.0;
+const GRID_SIZE: f32 = 10.0;
+const MIN_GRID_PIXELS: f32 = 8.0;
+//every nth minor line is a major one
+const GRID_MAJOR_EVERY: i64 = 5;
+const STATUS_SECS: f32 = 3.0;
+const DUPLICATE_OFFSET: f32 = 20.0;
+const DEFAULT_EXPORT_SCALE: &str = "2";
+//world units around the circuit in a whole-circuit export
+const EXPORT_MARGIN: f32 = 20.0;
+//longest side of an exported image, bigger ones get scaled down
+const MAX_EXPORT_PIXELS: f32 = 8192.0;
+const PALETTE_W: f32 = 160.0;
+const PALETTE_ROW: f32 = 20.0;
+const PALETTE_TOP: f32 = 40.0;
+const INSPECTOR_W: f32 = 230.0;
+const INSPECTOR_TOP: f32 = 60.0;
+const INSPECTOR_ROW: f32 = 16.0;
+const MIN_ZOOM: f32 = 0.125;
+const MAX_ZOOM: f32 = 64.0;
+const TOOLTIP_PADDING: f32 = 4.0;
 fn calculate_comp_height(node_type:sls::NodeType,max_pins: usize) -> f32 {
     let height: f32 = (max_pins as f32 * PIN_SPACING) + MIN_OUTER_PADDING;
     max(height, if node_type==NodeType::INTEGRATED_CIRCUIT {MIN_IC_COMP_SIZE}else{COMP_SIZE})
@@ -72,6 +330,40 @@ fn calculate_pin_height(num_of_pins: usize, comp_height: f32) -> f32 {
     let skip = real_padding / 2.0;
     skip + (PIN_SIZE)
 }
+//(input pins, output pins) in world coordinates
+pub(crate) fn calculate_pin_pos(comp: &sls::Component) -> (Vec<Vector2>, Vec<Vector2>) {
+    let to_num_in = sls::get_num_inputs(comp);
+    let to_num_out = comp.outputs.len();
+    let to_height = calculate_comp_height(comp.node_type,max(to_num_in, to_num_out));
+    let to_in_y_offset = calculate_pin_height(to_num_in, to_height);
+    let to_in_y = comp.y + to_in_y_offset;
+    let to_out_y_offset = calculate_pin_height(to_num_out, to_height);
+    let to_out_y = comp.y + to_out_y_offset;
+    let mut in_pin = Vec::with_capacity(to_num_in);
+    for i in 0..to_num_in {
+        let pin_pos =
+            Vector2::new(comp.x - PIN_LEN, to_in_y + (PIN_SPACING * i as f32));
+        in_pin.push(pin_pos);
+    }
+    let mut out_pin = Vec::with_capacity(to_num_out);
+    for i in 0..to_num_out {
+        let pin_pos =
+            Vector2::new(comp.x + get_comp_size(comp) + PIN_LEN, to_out_y + (PIN_SPACING * i as f32));
+        out_pin.push(pin_pos);
+    }
+    (in_pin, out_pin)
+}
+fn snap_to_grid(v: f32, step: f32) -> f32 {
+    (v / step).round() * step
+}
+//minor grid spacing in world units, doubled until the lines are far enough apart on screen
+fn grid_step(zoom: f32) -> f32 {
+    let mut step = GRID_SIZE;
+    while step * zoom < MIN_GRID_PIXELS {
+        step *= 2.0;
+    }
+    step
+}
 fn print_dyn(n: &Circuit, indent: usize) {
     println!("{:indent$}{} {}", ' ', &n.header.name, &n.has_dynamic);
     for comp in n
@@ -83,6 +375,37 @@ fn print_dyn(n: &Circuit, indent: usize) {
         print_dyn(instance, indent + 1);
     }
 }
+pub(crate) fn ic_of(comp: &sls::Component) -> &Circuit {
+    comp.ic_instance.as_ref().expect("ic_instance of INTEGRATED_CIRCUIT")
+}
+fn ic_of_mut(comp: &mut sls::Component) -> &mut Circuit {
+    comp.ic_instance.as_mut().expect("ic_instance of INTEGRATED_CIRCUIT")
+}
+fn circuit_at_mut<'a>(root: &'a mut Circuit, ic_path: &[usize]) -> &'a mut Circuit {
+    let mut c = root;
+    for &comp_i in ic_path {
+        c = ic_of_mut(&mut c.components[comp_i]);
+    }
+    c
+}
+//IC IDs back to indexes, None if one of them is gone
+fn resolve_ic_path(root: &Circuit, ids: &[ID]) -> Option<Vec<usize>> {
+    let mut ic_path = Vec::with_capacity(ids.len());
+    let mut c = root;
+    for id in ids {
+        let comp_i = c.components.iter().position(|comp| comp.get_id() == id && comp.ic_instance.is_some())?;
+        ic_path.push(comp_i);
+        c = ic_of(&c.components[comp_i]);
+    }
+    Some(ic_path)
+}
+fn circuit_at<'a>(root: &'a Circuit, ic_path: &[usize]) -> &'a Circuit {
+    let mut c = root;
+    for &comp_i in ic_path {
+        c = ic_of(&c.components[comp_i]);
+    }
+    c
+}
 fn get_comp_size(comp:&sls::Component) -> f32 {
     match comp.node_type {
         NodeType::PULSE_BUTTON|NodeType::TOGGLE_BUTTON => BUTTON_SIZE,
@@ -90,10 +413,400 @@ fn get_comp_size(comp:&sls::Component) -> f32 {
         _=>COMP_SIZE,
     }
 }
+//what's written under a component, its type if it has no label
+pub(crate) fn comp_label(comp: &sls::Component) -> String {
+    match comp.label.as_ref() {
+        Some(l) => l.clone(),
+        None => match comp.node_type {
+            NodeType::NOTE => "".to_string(),
+            _ => comp.node_type.to_string(),
+        },
+    }
+}
+pub(crate) fn get_comp_rect(comp:&sls::Component) -> Rectangle {
+    let height = calculate_comp_height(comp.node_type,max(sls::get_num_inputs(comp), comp.outputs.len()));
+    Rectangle::new(comp.x, comp.y, get_comp_size(comp), height)
+}
+fn distance_to_segment(p: Vector2, a: Vector2, b: Vector2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.x * ab.x + ab.y * ab.y;
+    if len_sq == 0.0 {
+        return p.distance_to(a);
+    }
+    let t = (((p.x - a.x) * ab.x + (p.y - a.y) * ab.y) / len_sq).clamp(0.0, 1.0);
+    p.distance_to(a + ab.scale_by(t))
+}
+fn distance_to_path(p: Vector2, path: &[Vector2]) -> f32 {
+    path.windows(2).map(|seg| distance_to_segment(p, seg[0], seg[1])).fold(f32::INFINITY, f32::min)
+}
+//name cut down to max_chars and to fit in width, with ".." where it was cut
+fn fit_text(name: &str, max_chars: usize, width: f32) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut keep = chars.len().min(max_chars);
+    loop {
+        let text: String = if keep < chars.len() {
+            chars[..keep].iter().collect::<String>() + ".."
+        } else {
+            name.to_string()
+        };
+        if keep == 0 || measure_text(&text, PIN_NAME_SIZE) as f32 <= width {
+            return text;
+        }
+        keep -= 1;
+    }
+}
+//where a wire's bus label goes, the middle of its middle segment
+fn bus_anchor(route: &[Vector2]) -> Vector2 {
+    let k = (route.len() - 1) / 2;
+    (route[k] + route[k + 1]).scale_by(0.5)
+}
+//bits as hex, least significant first in the input
+fn bus_hex(bits: &[bool]) -> String {
+    let digits: String = bits
+        .chunks(4)
+        .rev()
+        .map(|nibble| {
+            let value = nibble.iter().enumerate().fold(0, |acc, (k, &b)| acc | ((b as u32) << k));
+            std::char::from_digit(value, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    format!("0x{}", digits)
+}
+//true for a wire drawn as part of a bus line instead of on its own
+fn bus_collapsed(buses: &[Bus], expanded: &[(usize, usize)], bus: Option<usize>) -> bool {
+    bus.is_some_and(|bus_i| !expanded.contains(&(buses[bus_i].src, buses[bus_i].sink)))
+}
+//route a bus is drawn along, the one of its lowest bit
+fn bus_route<'a>(bus: &Bus, comp_inputs: &'a [Vec<CompInput>]) -> &'a [Vector2] {
+    let input = comp_inputs[bus.sink].iter().find(|input| input.in_pin == bus.bits[0]).unwrap();
+    &input.route
+}
+//path of the wire from src's output pin at `from` to sink's input pin at `to`
+pub(crate) fn wire_route(orthogonal: bool, rects: &[Rectangle], src: usize, sink: usize, from: Vector2, to: Vector2) -> Vec<Vector2> {
+    if !orthogonal {
+        return vec![from, to];
+    }
+    route::route(from, to, &route::obstacles_near(from, to, rects, [src, sink]))
+}
+//smallest rect around the components and their pins
+pub(crate) fn bounds_of<'a>(comps: impl Iterator<Item = &'a sls::Component>) -> Option<Rectangle> {
+    const PIN_REACH: f32 = PIN_LEN + PIN_SIZE;
+    comps
+        .map(|comp| {
+            let rect = get_comp_rect(comp);
+            Rectangle::new(rect.x - PIN_REACH, rect.y, rect.width + PIN_REACH * 2.0, rect.height)
+        })
+        .reduce(|a, b| {
+            let x = a.x.min(b.x);
+            let y = a.y.min(b.y);
+            let right = (a.x + a.width).max(b.x + b.width);
+            let bottom = (a.y + a.height).max(b.y + b.height);
+            Rectangle::new(x, y, right - x, bottom - y)
+        })
+}
+//case-insensitive match on label, type name and note text
+fn search_hits(circuit: &Circuit, labels: &[String], query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let query = query.to_lowercase();
+    circuit
+        .components
+        .iter()
+        .enumerate()
+        .filter(|(comp_i, comp)| {
+            labels[*comp_i].to_lowercase().contains(&query)
+                || comp.node_type.to_string().to_lowercase().contains(&query)
+                || comp.text.as_ref().is_some_and(|t| t.to_lowercase().contains(&query))
+        })
+        .map(|(comp_i, _)| comp_i)
+        .collect()
+}
+fn bool_str(b: bool) -> &'static str {
+    if b { "1" } else { "0" }
+}
+//everything draw_world reads, borrowed field by field so the draw handle can keep rl
+struct World<'a> {
+    //the circuit on screen
+    circuit: &'a Circuit,
+    settings: &'a Settings,
+    comp_labels: &'a [String],
+    in_pin_pos: &'a [Vec<Vector2>],
+    out_pin_pos: &'a [Vec<Vector2>],
+    comp_inputs: &'a [Vec<CompInput>],
+    comp_outputs: &'a [Vec<CompOutput>],
+    ic_pin_names: &'a [(Vec<String>, Vec<String>)],
+    buses: &'a [Bus],
+    expanded_buses: &'a [(usize, usize)],
+    highlight: Option<Net>,
+    text_edit: Option<&'a TextEdit>,
+}
+//selection and editing feedback, exports leave it out
+struct Overlays<'a> {
+    selected: &'a [usize],
+    selected_wire: Option<(usize, usize)>,
+    box_select: Option<Vector2>,
+    wiring: Option<Net>,
+    search_hit: Option<usize>,
+    //the pointer in world space
+    mouse: Vector2,
+}
+//the circuit in world space on any draw target, the screen or a render texture
+//screen_rect is the part of the world being drawn through cam
+fn draw_world(d: &mut impl RaylibDraw, world: &World, overlays: Option<&Overlays>, cam: Camera2D, screen_rect: Rectangle, theme: &Theme) {
+    if world.settings.show_grid {
+        let step = grid_step(cam.zoom);
+        let thickness = 1.0 / cam.zoom;
+        let first_x = (screen_rect.x / step).floor() as i64;
+        let last_x = ((screen_rect.x + screen_rect.width) / step).ceil() as i64;
+        for i in first_x..=last_x {
+            let x = i as f32 * step;
+            let color = if i % GRID_MAJOR_EVERY == 0 { theme.grid_major } else { theme.grid_minor };
+            d.draw_line_ex(Vector2::new(x, screen_rect.y), Vector2::new(x, screen_rect.y + screen_rect.height), thickness, color);
+        }
+        let first_y = (screen_rect.y / step).floor() as i64;
+        let last_y = ((screen_rect.y + screen_rect.height) / step).ceil() as i64;
+        for i in first_y..=last_y {
+            let y = i as f32 * step;
+            let color = if i % GRID_MAJOR_EVERY == 0 { theme.grid_major } else { theme.grid_minor };
+            d.draw_line_ex(Vector2::new(screen_rect.x, y), Vector2::new(screen_rect.x + screen_rect.width, y), thickness, color);
+        }
+    }
+    let c = world.circuit;
+    for (comp_i, comp) in c.components.iter().enumerate() {
+        let to_num_in = sls::get_num_inputs(comp);
+        let to_num_out = comp.outputs.len();
+        let to_height = calculate_comp_height(comp.node_type,max(to_num_in, to_num_out));
+        let editing = world.text_edit.filter(|e| e.comp == comp_i);
+        let label = match editing {
+            Some(edit) if edit.target == TextTarget::Label => &edit.text,
+            _ => &world.comp_labels[comp_i],
+        };
+        let size = measure_text(label, LABEL_SIZE);
+        //TODO actually make sure label is below ic's
+        d.draw_text(
+            label,
+            (comp.x + (MIN_IC_COMP_SIZE / 2.0)) as i32 - (size / 2),
+            (comp.y + to_height) as i32,
+            LABEL_SIZE,
+            theme.text,
+        );
+        //draw wires
+        //wire goes *from* one component *to* this component
+        //plus components have *in*put pins and *out*put pins
+        let to_in_y_offset = calculate_pin_height(to_num_in, to_height);
+        let to_in_y = comp.y + to_in_y_offset;
+        let to_out_y_offset = calculate_pin_height(to_num_out, to_height);
+        let to_out_y = comp.y + to_out_y_offset;
+        for i in 0..to_num_in {
+            let pin_pos =
+                Vector2::new(comp.x - PIN_LEN, to_in_y + (PIN_SPACING * i as f32));
+            let pin_pos_line = Vector2::new(
+                comp.x - PIN_LEN + PIN_SIZE,
+                to_in_y + (PIN_SPACING * i as f32),
+            );
+            let comp_pos = Vector2::new(comp.x, to_in_y + (PIN_SPACING * i as f32));
+            if screen_rect.check_collision_point_rec(pin_pos) {
+                d.draw_line_ex(pin_pos_line, comp_pos, WIRE_THICKNES, theme.pin);
+                d.draw_circle_lines_v(pin_pos, PIN_SIZE, theme.pin);
+            }
+        }
+        if comp.node_type != NodeType::LIGHT_BULB {
+            for i in 0..to_num_out {
+                let comp_size = get_comp_size(comp);
+                let comp_right = comp.x+comp_size;
+                let pin_pos = Vector2::new(
+                    comp_right + PIN_LEN,
+                    to_out_y + (PIN_SPACING * i as f32),
+                );
+                let pin_pos_line = Vector2::new(
+                    (comp_right + PIN_LEN) - PIN_SIZE,
+                    to_out_y + (PIN_SPACING * i as f32),
+                );
+                let comp_pos = Vector2::new(
+                    comp_right,
+                    to_out_y + (PIN_SPACING * i as f32),
+                );
+                if screen_rect.check_collision_point_rec(pin_pos) {
+                    d.draw_line_ex(pin_pos_line, comp_pos, WIRE_THICKNES, theme.pin);
+                    d.draw_circle_lines_v(pin_pos, PIN_SIZE, theme.pin);
+                }
+            }
+        }
+        for input in world.comp_inputs[comp_i].iter().filter(|input| !bus_collapsed(&world.buses, &world.expanded_buses, input.bus)) {
+            let on = match comp.input_states.get(input.in_pin){Some(s)=>*s,None=>panic!("tried to get {} of {:#?}",&input.in_pin,comp)};
+            let color = if on { theme.wire_on } else { theme.wire_off };
+            if input.route.iter().any(|p| screen_rect.check_collision_point_rec(*p)) {
+                for seg in input.route.windows(2) {
+                    d.draw_line_ex(seg[0], seg[1], WIRE_THICKNES, color);
+                }
+            }
+        }
+        match comp.node_type {
+            sls::NodeType::LIGHT_BULB => {
+                let b: bool = comp.outputs[0];
+                let color = if b { theme.bulb_on } else { theme.bulb_off };
+                const LIGHT_RADIUS: f32 = COMP_SIZE / 2.0;
+                let pos = Vector2::new(comp.x + LIGHT_RADIUS, comp.y + LIGHT_RADIUS);
+                d.draw_circle_v(pos, LIGHT_RADIUS, theme.bulb_rim);
+                d.draw_circle_v(pos, LIGHT_RADIUS - BUTTON_BORDER, color);
+            }
+            sls::NodeType::PULSE_BUTTON => {
+                let b: bool = comp.outputs[0];
+                let color = if b { theme.button_on } else { theme.button_off };
+                const BUTTON_RADIUS: f32 = BUTTON_SIZE / 2.0;
+                let pos = Vector2::new(comp.x + BUTTON_RADIUS, comp.y + BUTTON_RADIUS);
+                d.draw_circle_v(pos, BUTTON_RADIUS, theme.button_rim);
+                d.draw_circle_v(pos, BUTTON_RADIUS - BUTTON_BORDER, color);
+            }
+            sls::NodeType::TOGGLE_BUTTON => {
+                let b: bool = comp.outputs[0];
+                let color = if b { theme.button_on } else { theme.button_off };
+                let pos = Vector2::new(comp.x, comp.y);
+                d.draw_rectangle_v(
+                    pos,
+                    Vector2::new(BUTTON_SIZE, BUTTON_SIZE),
+                    theme.button_rim,
+                );
+                let pos = Vector2::new(comp.x + BUTTON_BORDER, comp.y + BUTTON_BORDER);
+                d.draw_rectangle_v(
+                    pos,
+                    Vector2::new(
+                        BUTTON_SIZE - (BUTTON_BORDER * 2.0),
+                        BUTTON_SIZE - (BUTTON_BORDER * 2.0),
+                    ),
+                    color,
+                );
+            }
+            sls::NodeType::NOTE => {
+                let text: &str = match editing {
+                    Some(edit) if edit.target == TextTarget::Note => &edit.text,
+                    _ => comp.text.as_ref().expect("text field of NODE"),
+                };
+                d.draw_text(text, comp.x as i32, comp.y as i32, world.settings.note_size, theme.text);
+            }
+            sls::NodeType::SEVEN_SEGMENT_DISPLAY => {
+                let rect = Rectangle::new(comp.x, comp.y, COMP_SIZE, to_height);
+                shapes::draw_seven_segment(d, rect, &comp.input_states, theme.display, theme.segment_on, theme.segment_off);
+            }
+            NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => {
+                let rect = Rectangle::new(comp.x, comp.y, COMP_SIZE, to_height);
+                shapes::draw_decoder(d, rect, &world.in_pin_pos[comp_i], &world.out_pin_pos[comp_i], theme.gate_fill, theme.gate_line);
+            }
+            node_type if shapes::is_gate(node_type) => {
+                let rect = Rectangle::new(comp.x, comp.y, COMP_SIZE, to_height);
+                shapes::draw_gate(d, node_type, rect, world.settings.gate_style, theme.gate_fill, theme.gate_line);
+            }
+            _ => {
+                let color = if let Some(ic) = &comp.ic_instance {
+                    if ic.comps_changed {theme.ic_changed}else{theme.ic}
+                } else {
+                    theme.gate_fill
+                };
+                let pos = Vector2::new(comp.x, comp.y);
+                d.draw_rectangle_v(pos, Vector2::new(COMP_SIZE, to_height), color);
+                if PIN_NAME_SIZE as f32 * cam.zoom >= MIN_PIN_NAME_PIXELS {
+                    let (in_names, out_names) = &world.ic_pin_names[comp_i];
+                    let max_chars = max((PIN_NAME_CHARS * cam.zoom.min(1.0)) as usize, 1);
+                    let half = COMP_SIZE / 2.0 - 2.0;
+                    let half_y = PIN_NAME_SIZE as f32 / 2.0;
+                    for (name, pin) in in_names.iter().zip(&world.in_pin_pos[comp_i]) {
+                        let name = fit_text(name, max_chars, half);
+                        d.draw_text(&name, (comp.x + 2.0) as i32, (pin.y - half_y) as i32, PIN_NAME_SIZE, theme.ic_text);
+                    }
+                    for (name, pin) in out_names.iter().zip(&world.out_pin_pos[comp_i]) {
+                        let name = fit_text(name, max_chars, half);
+                        let width = measure_text(&name, PIN_NAME_SIZE) as f32;
+                        d.draw_text(&name, (comp.x + COMP_SIZE - 2.0 - width) as i32, (pin.y - half_y) as i32, PIN_NAME_SIZE, theme.ic_text);
+                    }
+                }
+            }
+        }
+        
+    }
+    for (bus_i, bus) in world.buses.iter().enumerate() {
+        let route = bus_route(bus, &world.comp_inputs);
+        if !route.iter().any(|p| screen_rect.check_collision_point_rec(*p)) {
+            continue;
+        }
+        let collapsed = bus_collapsed(&world.buses, &world.expanded_buses, Some(bus_i));
+        if collapsed {
+            for seg in route.windows(2) {
+                d.draw_line_ex(seg[0], seg[1], BUS_THICKNES, theme.bus);
+            }
+        }
+        let states = &c.components[bus.sink].input_states;
+        let bits: Vec<bool> = bus.bits.iter().map(|&pin| states.get(pin).copied().unwrap_or(false)).collect();
+        let text = bus_hex(&bits);
+        let width = measure_text(&text, BUS_LABEL_SIZE) as f32;
+        let anchor = bus_anchor(route);
+        let rect = Rectangle::new(anchor.x - width / 2.0 - 2.0, anchor.y - BUS_LABEL_SIZE as f32 / 2.0 - 1.0, width + 4.0, BUS_LABEL_SIZE as f32 + 2.0);
+        d.draw_rectangle_rec(rect, theme.background);
+        d.draw_rectangle_lines_ex(rect, 1.0, theme.bus);
+        let color = if collapsed { theme.bus } else { theme.pin };
+        d.draw_text(&text, (rect.x + 2.0) as i32, (rect.y + 1.0) as i32, BUS_LABEL_SIZE, color);
+    }
+    //dim everything, then draw the highlighted net on top
+    if let Some(net) = world.highlight {
+        d.draw_rectangle_rec(screen_rect, theme.dim);
+        let source = world.out_pin_pos[net.comp][net.pin];
+        for output in world.comp_outputs[net.comp].iter().filter(|output| output.out_pin == net.pin) {
+            let sink = world.in_pin_pos[output.other_comp][output.other_pin];
+            if let Some(input) = world.comp_inputs[output.other_comp].iter().find(|input| input.in_pin == output.other_pin) {
+                for seg in input.route.windows(2) {
+                    d.draw_line_ex(seg[0], seg[1], HIGHLIGHT_THICKNES, theme.highlight);
+                }
+            }
+            d.draw_circle_v(sink, PIN_SIZE, theme.highlight);
+        }
+        d.draw_rectangle_lines_ex(get_comp_rect(&c.components[net.comp]), 2.0, theme.highlight);
+        d.draw_circle_v(source, PIN_SIZE, theme.highlight);
+    }
+    if let Some(overlays) = overlays {
+        for &comp_i in overlays.selected {
+            d.draw_rectangle_lines_ex(get_comp_rect(&c.components[comp_i]), 2.0, theme.select);
+        }
+        if let Some((comp_i, in_pin)) = overlays.selected_wire {
+            if let Some(input) = world.comp_inputs[comp_i].iter().find(|input| input.in_pin == in_pin) {
+                for seg in input.route.windows(2) {
+                    d.draw_line_ex(seg[0], seg[1], HIGHLIGHT_THICKNES, theme.select);
+                }
+            }
+        }
+        //caret for the text being edited, position fields have theirs in the inspector
+        if let Some(edit) = world.text_edit.filter(|e| matches!(e.target, TextTarget::Label | TextTarget::Note)) {
+            let comp = &c.components[edit.comp];
+            let (size, origin) = match edit.target {
+                TextTarget::Label => {
+                    let width = measure_text(&edit.text, LABEL_SIZE) as f32;
+                    let x = comp.x + (MIN_IC_COMP_SIZE / 2.0) - (width / 2.0);
+                    (LABEL_SIZE, Vector2::new(x, comp.y + get_comp_rect(comp).height))
+                }
+                _ => (world.settings.note_size, Vector2::new(comp.x, comp.y)),
+            };
+            let (line, before) = edit.cursor_line();
+            let x = origin.x + measure_text(before, size) as f32;
+            let y = origin.y + (line as i32 * size) as f32;
+            d.draw_line_ex(Vector2::new(x, y), Vector2::new(x, y + size as f32), 1.0, theme.text);
+        }
+        if let Some(start) = overlays.box_select {
+            let end = overlays.mouse;
+            let rect = Rectangle::new(start.x.min(end.x), start.y.min(end.y), (start.x - end.x).abs(), (start.y - end.y).abs());
+            d.draw_rectangle_rec(rect, theme.box_select);
+            d.draw_rectangle_lines_ex(rect, 1.0 / cam.zoom, theme.select);
+        }
+        if let Some(from) = overlays.wiring {
+            d.draw_line_ex(world.out_pin_pos[from.comp][from.pin], overlays.mouse, WIRE_THICKNES, theme.select);
+        }
+        if let Some(comp_i) = overlays.search_hit {
+            d.draw_rectangle_lines_ex(get_comp_rect(&c.components[comp_i]), 3.0, theme.select);
+        }
+    }
+}
 impl State {
-    pub fn new() -> Self {
-        let circ = include_str!("../sls/prog-proc-8-bit.slj");
-        let mut n: sls::Circuit = serde_json::from_str(circ).unwrap();
+    //circuit_path is where the circuit was read from, None for the built in one
+    pub fn new(mut n: sls::Circuit, circuit_path: Option<String>) -> Self {
+        let data = data_path(circuit_path.as_deref());
         n.init_circ(None);
         n.tick(true);
         let cam = Camera2D {
@@ -114,58 +827,12 @@ impl State {
         rl.set_gestures_enabled(
             Gesture::GESTURE_HOLD as u32
                 | Gesture::GESTURE_TAP as u32
+                | Gesture::GESTURE_DOUBLETAP as u32
                 | Gesture::GESTURE_PINCH_OUT as u32
                 | Gesture::GESTURE_PINCH_IN as u32,
         );
-        let mut labels:Vec<String> = Vec::with_capacity(n.components.len());
-        for comp in &n.components {
-            labels.push(match comp.label.as_ref() {
-                Some(l) => l.clone(),
-                None => match comp.node_type {
-                    NodeType::NOTE => "".to_string(),
-                    _ => comp.node_type.to_string(),
-                },
-            });
-        }
-        let mut in_pin_pos:Vec<Vec<Vector2>> = Vec::with_capacity(n.components.len());
-        let mut out_pin_pos:Vec<Vec<Vector2>> = Vec::with_capacity(n.components.len());
-        for comp in &n.components {
-            let to_num_in = sls::get_num_inputs(comp);
-            let to_num_out = comp.outputs.len();
-            let to_height = calculate_comp_height(comp.node_type,max(to_num_in, to_num_out));
-            let to_in_y_offset = calculate_pin_height(to_num_in, to_height);
-            let to_in_y = comp.y + to_in_y_offset;
-            let to_out_y_offset = calculate_pin_height(to_num_out, to_height);
-            let to_out_y = comp.y + to_out_y_offset;
-            let mut in_pin = Vec::with_capacity(to_num_in);
-            for i in 0..to_num_in {
-                let pin_pos =
-                    Vector2::new(comp.x - PIN_LEN, to_in_y + (PIN_SPACING * i as f32));
-                in_pin.push(pin_pos);
-            }
-            in_pin_pos.push(in_pin);
-            let mut out_pin = Vec::with_capacity(to_num_out);
-            for i in 0..to_num_out {
-                let pin_pos =
-                    Vector2::new(comp.x + get_comp_size(comp) + PIN_LEN, to_out_y + (PIN_SPACING * i as f32));
-                out_pin.push(pin_pos);
-            }
-            out_pin_pos.push(out_pin);
-        }
-        let mut comp_inputs = Vec::with_capacity(n.components.len());
-        for comp in &n.components {
-            let mut inputs = Vec::with_capacity(comp.inputs.len());
-            for input in &comp.inputs {
-                inputs.push(CompInput {
-                    in_pin: input.in_pin,
-                    other_pin: input.other_pin,
-                    other_comp: n.components.iter().enumerate().find(|(_,n)|n.get_id()==&input.other_id).unwrap().0
-                });
-            }
-            comp_inputs.push(inputs);
-        }
         println!("init done!");
-        State {
+        let mut state = State {
             rl,
             t,
             circuit: n,
@@ -175,17 +842,882 @@ impl State {
             initial_distance: 0.0,
             initial_zoom: 1.0,
             initial_origin: Vector2::zero(),
-            settings: Settings { zoom_style: ZoomStyle::Mid },
+            settings: Settings {
+                zoom_style: ZoomStyle::Mid,
+                note_size: theme::load_note_size().unwrap_or(DEFAULT_NOTE_SIZE).max(MIN_NOTE_SIZE),
+                show_minimap: true,
+                show_inspector: true,
+                show_grid: true,
+                edit_mode: false,
+                snap_to_grid: true,
+                orthogonal_wires: true,
+                gate_style: GateStyle::Box,
+                theme: theme::load(),
+            },
             pointer_on_button: false,
-            comp_labels: labels,
-            in_pin_pos,
-            out_pin_pos,
-            comp_inputs,
+            comp_labels: Vec::new(),
+            in_pin_pos: Vec::new(),
+            out_pin_pos: Vec::new(),
+            comp_inputs: Vec::new(),
+            comp_outputs: Vec::new(),
+            ic_pin_names: Vec::new(),
             tick_rate: 1.0/10.,
             begin: Instant::now(),
             run_times:0,
+            hover: None,
+            highlight: None,
+            search: None,
+            cam_anim: None,
+            selected: Vec::new(),
+            pointer_on_minimap: false,
+            ic_path: Vec::new(),
+            bookmarks: bookmarks::load(&data),
+            naming: None,
+            moving: None,
+            //the ICs that come with the built in circuit are always there too
+            palette: edit::load_palette(&[library_dir(&data), library_dir(CIRCUIT_PATH)]),
+            palette_scroll: 0.0,
+            placing: None,
+            wiring: None,
+            selected_wire: None,
+            status: None,
+            history: History::default(),
+            box_select: None,
+            clipboard: None,
+            text_edit: None,
+            buses: Vec::new(),
+            bus_rules: buses::load(&data),
+            expanded_buses: Vec::new(),
+            export_prompt: None,
+            circuit_path,
+            save_prompt: None,
+            pending_export: None,
+        };
+        state.rebuild_caches();
+        state.fit_all();
+        //no need to animate the first view
+        if let Some(anim) = state.cam_anim.take() {
+            state.cam.target = anim.to_target;
+            state.cam.zoom = anim.to_zoom;
+        }
+        state
+    }
+    //the circuit currently on screen, the root or an IC drilled into
+    fn circ(&self) -> &Circuit {
+        circuit_at(&self.circuit, &self.ic_path)
+    }
+    fn circ_mut(&mut self) -> &mut Circuit {
+        circuit_at_mut(&mut self.circuit, &self.ic_path)
+    }
+    //recomputes everything derived from the layout of circ()
+    fn rebuild_caches(&mut self) {
+        let ic_ids = self.ic_path_ids();
+        let c = circuit_at(&self.circuit, &self.ic_path);
+        let labels: Vec<String> = c.components.iter().map(comp_label).collect();
+        let mut in_pin_pos:Vec<Vec<Vector2>> = Vec::with_capacity(c.components.len());
+        let mut out_pin_pos:Vec<Vec<Vector2>> = Vec::with_capacity(c.components.len());
+        for comp in &c.components {
+            let (in_pin, out_pin) = calculate_pin_pos(comp);
+            in_pin_pos.push(in_pin);
+            out_pin_pos.push(out_pin);
+        }
+        let rects: Vec<Rectangle> = c.components.iter().map(get_comp_rect).collect();
+        let mut comp_inputs = Vec::with_capacity(c.components.len());
+        for (comp_i, comp) in c.components.iter().enumerate() {
+            let mut inputs = Vec::with_capacity(comp.inputs.len());
+            for input in &comp.inputs {
+                let other_comp = c.components.iter().enumerate().find(|(_,n)|n.get_id()==&input.other_id).unwrap().0;
+                let from = out_pin_pos[other_comp][input.other_pin];
+                let to = in_pin_pos[comp_i][input.in_pin];
+                inputs.push(CompInput {
+                    in_pin: input.in_pin,
+                    other_pin: input.other_pin,
+                    other_comp,
+                    route: wire_route(self.settings.orthogonal_wires, &rects, other_comp, comp_i, from, to),
+                    bus: None,
+                });
+            }
+            comp_inputs.push(inputs);
+        }
+        let wires: Vec<buses::Wire> = comp_inputs
+            .iter()
+            .enumerate()
+            .flat_map(|(comp_i, inputs)| inputs.iter().map(move |input| (input.other_comp, input.other_pin, comp_i, input.in_pin)))
+            .collect();
+        let index_of = |id: &ID| c.components.iter().position(|comp| comp.get_id() == id);
+        let rules: Vec<(usize, usize, bool)> = self
+            .bus_rules
+            .iter()
+            .filter(|rule| rule.ic_path == ic_ids)
+            .filter_map(|rule| Some((index_of(&rule.src)?, index_of(&rule.sink)?, rule.grouped)))
+            .collect();
+        let found = buses::find_buses(&wires, &rules);
+        for (bus_i, bus) in found.iter().enumerate() {
+            for input in comp_inputs[bus.sink].iter_mut().filter(|input| bus.bits.contains(&input.in_pin)) {
+                input.bus = Some(bus_i);
+            }
+        }
+        let mut comp_outputs: Vec<Vec<CompOutput>> = (0..c.components.len()).map(|_| Vec::new()).collect();
+        for (comp_i, inputs) in comp_inputs.iter().enumerate() {
+            for input in inputs {
+                comp_outputs[input.other_comp].push(CompOutput {
+                    out_pin: input.other_pin,
+                    other_pin: input.in_pin,
+                    other_comp: comp_i,
+                });
+            }
+        }
+        self.comp_labels = labels;
+        self.in_pin_pos = in_pin_pos;
+        self.out_pin_pos = out_pin_pos;
+        self.comp_inputs = comp_inputs;
+        self.comp_outputs = comp_outputs;
+        self.ic_pin_names = c
+            .components
+            .iter()
+            .map(|comp| match &comp.ic_instance {
+                Some(_) => {
+                    let ic = ic_of(comp);
+                    let (ins, outs) = edit::ic_pin_comps(ic);
+                    let name = |i: &usize| ic.components[*i].label.clone().unwrap_or_default();
+                    (ins.iter().map(name).collect(), outs.iter().map(name).collect())
+                }
+                None => (Vec::new(), Vec::new()),
+            })
+            .collect();
+        self.expanded_buses.retain(|pair| found.iter().any(|bus| (bus.src, bus.sink) == *pair));
+        self.buses = found;
+    }
+    //the simulation keeps its own lookup tables, so anything that changes what exists
+    //or what's connected goes back through init_circ
+    fn resync(&mut self) {
+        self.circuit.init_circ(None);
+        self.circuit.comps_changed = true;
+        self.rebuild_caches();
+    }
+    fn fit_all(&mut self) {
+        if let Some(rect) = bounds_of(self.circ().components.iter()) {
+            self.focus_rect(rect, FIT_FILL);
+        }
+    }
+    fn zoom_to_selection(&mut self) {
+        let c = self.circ();
+        if let Some(rect) = bounds_of(self.selected.iter().map(|&comp_i| &c.components[comp_i])) {
+            self.focus_rect(rect, FIT_FILL);
         }
     }
+    //animates the camera so rect is centered and fills `fill` of the screen
+    fn focus_rect(&mut self, rect: Rectangle, fill: f32) {
+        let w = self.rl.get_render_width() as f32;
+        let h = self.rl.get_render_height() as f32;
+        let center = Vector2::new(w / 2.0, h / 2.0);
+        //move the offset to the middle without moving the view
+        let from_target = self.rl.get_screen_to_world2D(center, self.cam);
+        self.cam.offset = center;
+        self.cam.target = from_target;
+        let zoom = (w * fill / max(rect.width, 1.0)).min(h * fill / max(rect.height, 1.0));
+        self.cam_anim = Some(CamAnim {
+            from_target,
+            from_zoom: self.cam.zoom,
+            to_target: Vector2::new(rect.x + rect.width / 2.0, rect.y + rect.height / 2.0),
+            to_zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            start: Instant::now(),
+        });
+    }
+    fn update_cam_anim(&mut self) {
+        let Some(anim) = &self.cam_anim else { return };
+        //the user grabbed the camera
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) || self.rl.get_mouse_wheel_move() != 0.0 {
+            self.cam_anim = None;
+            return;
+        }
+        let t = (anim.start.elapsed().as_secs_f32() / CAM_ANIM_SECS).min(1.0);
+        let e = t * (2.0 - t);
+        self.cam.target = anim.from_target + (anim.to_target - anim.from_target).scale_by(e);
+        //lerp in log space like the scroll zoom
+        self.cam.zoom = (anim.from_zoom.ln() + (anim.to_zoom.ln() - anim.from_zoom.ln()) * e).exp();
+        if t >= 1.0 {
+            self.cam_anim = None;
+        }
+    }
+    fn minimap_view(&self) -> Option<MinimapView> {
+        let world = bounds_of(self.circ().components.iter())?;
+        let w = self.rl.get_render_width() as f32;
+        let h = self.rl.get_render_height() as f32;
+        let size = Vector2::new(w * MINIMAP_FRACTION, h * MINIMAP_FRACTION);
+        let screen = Rectangle::new(MINIMAP_MARGIN, h - size.y - MINIMAP_MARGIN, size.x, size.y);
+        let scale = (size.x / max(world.width, 1.0)).min(size.y / max(world.height, 1.0));
+        Some(MinimapView { screen, world, scale })
+    }
+    //centers the camera on the world point under the pointer
+    fn update_minimap(&mut self, mouse_pos: Vector2) {
+        if self.rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.pointer_on_minimap = false;
+        }
+        if let Some(view) = self.minimap_view() {
+            self.cam_anim = None;
+            self.cam.offset = Vector2::new(
+                self.rl.get_render_width() as f32 / 2.0,
+                self.rl.get_render_height() as f32 / 2.0,
+            );
+            self.cam.target = view.to_world(mouse_pos);
+        }
+    }
+    fn screen_center(&self) -> Vector2 {
+        Vector2::new(
+            self.rl.get_render_width() as f32 / 2.0,
+            self.rl.get_render_height() as f32 / 2.0,
+        )
+    }
+    fn enter_ic(&mut self, comp_i: usize) {
+        if self.circ().components[comp_i].node_type != NodeType::INTEGRATED_CIRCUIT {
+            return;
+        }
+        self.leave_circuit();
+        self.ic_path.push(comp_i);
+        self.forget_indexes();
+        self.rebuild_caches();
+        self.fit_all();
+    }
+    fn exit_ic(&mut self) {
+        if self.ic_path.is_empty() {
+            return;
+        }
+        self.leave_circuit();
+        if let Some(comp_i) = self.ic_path.pop() {
+            self.forget_indexes();
+            self.rebuild_caches();
+            self.selected = vec![comp_i];
+            self.zoom_to_selection();
+        }
+    }
+    //text edits and search hits point into the circuit on screen, so they end before it changes
+    fn leave_circuit(&mut self) {
+        self.finish_text_edit(true);
+        if self.search.is_some() {
+            self.close_search();
+        }
+    }
+    //ic_path as IDs, which survive edits that shift indexes
+    fn ic_path_ids(&self) -> Vec<ID> {
+        let mut ids = Vec::with_capacity(self.ic_path.len());
+        let mut c = &self.circuit;
+        for &comp_i in &self.ic_path {
+            ids.push(c.components[comp_i].get_id().clone());
+            c = ic_of(&c.components[comp_i]);
+        }
+        ids
+    }
+    fn save_bookmark(&mut self, slot: usize, name: String) {
+        self.bookmarks[slot] = Some(Bookmark {
+            name,
+            target: self.rl.get_screen_to_world2D(self.screen_center(), self.cam),
+            zoom: self.cam.zoom,
+            ic_path: self.ic_path_ids(),
+        });
+        if let Err(e) = bookmarks::save(&self.data_path(), &self.bookmarks) {
+            self.set_status(e);
+        }
+    }
+    fn recall_bookmark(&mut self, slot: usize) {
+        let Some(bookmark) = &self.bookmarks[slot] else { return };
+        //IDs back to indexes, in case the circuit changed since it was saved
+        let Some(ic_path) = resolve_ic_path(&self.circuit, &bookmark.ic_path) else {
+            println!("view {} points into an IC that's gone", bookmark.name);
+            return;
+        };
+        let (target, zoom) = (bookmark.target, bookmark.zoom);
+        if ic_path != self.ic_path {
+            self.leave_circuit();
+            self.ic_path = ic_path;
+            self.forget_indexes();
+            self.rebuild_caches();
+        }
+        let center = self.screen_center();
+        let from_target = self.rl.get_screen_to_world2D(center, self.cam);
+        self.cam.offset = center;
+        self.cam.target = from_target;
+        self.cam_anim = Some(CamAnim {
+            from_target,
+            from_zoom: self.cam.zoom,
+            to_target: target,
+            to_zoom: zoom,
+            start: Instant::now(),
+        });
+    }
+    fn update_naming(&mut self) {
+        let Some((slot, name)) = self.naming.as_mut() else { return };
+        while let Some(c) = self.rl.get_char_pressed() {
+            name.push(c);
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            name.pop();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            let (slot, name) = (*slot, std::mem::take(name));
+            self.naming = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            self.save_bookmark(slot, name);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.naming = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+        }
+    }
+    fn open_search(&mut self) {
+        if self.search.is_none() {
+            self.search = Some(Search { query: String::new(), hits: Vec::new(), selected: 0 });
+        }
+        //escape closes the search instead of the window
+        self.rl.set_exit_key(None);
+    }
+    fn close_search(&mut self) {
+        self.search = None;
+        self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+    }
+    fn update_search(&mut self) {
+        let Some(search) = self.search.as_mut() else { return };
+        let mut changed = false;
+        while let Some(c) = self.rl.get_char_pressed() {
+            search.query.push(c);
+            changed = true;
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            changed |= search.query.pop().is_some();
+        }
+        if changed {
+            search.hits = search_hits(circuit_at(&self.circuit, &self.ic_path), &self.comp_labels, &search.query);
+            search.selected = 0;
+        }
+        let shown = search.hits.len().min(SEARCH_MAX_SHOWN);
+        if self.rl.is_key_pressed(KeyboardKey::KEY_DOWN) && shown > 0 {
+            search.selected = (search.selected + 1) % shown;
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_UP) && shown > 0 {
+            search.selected = (search.selected + shown - 1) % shown;
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            if let Some(&comp_i) = search.hits.get(search.selected) {
+                let rect = get_comp_rect(&self.circ().components[comp_i]);
+                self.selected = vec![comp_i];
+                self.focus_rect(rect, 0.25);
+            }
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.close_search();
+        }
+    }
+    fn label_of(&self, comp_i: usize) -> String {
+        let label = &self.comp_labels[comp_i];
+        if label.is_empty() {
+            self.circ().components[comp_i].node_type.to_string()
+        } else {
+            label.clone()
+        }
+    }
+    //where the label under a component is drawn
+    fn label_rect(&self, comp_i: usize, label: &str) -> Rectangle {
+        let comp = &self.circ().components[comp_i];
+        let rect = get_comp_rect(comp);
+        let size = self.rl.measure_text(label, LABEL_SIZE) as f32;
+        let lines = label.lines().count().max(1) as f32;
+        Rectangle::new(comp.x + (MIN_IC_COMP_SIZE / 2.0) - (size / 2.0), comp.y + rect.height, size, LABEL_SIZE as f32 * lines)
+    }
+    fn note_rect(&self, comp_i: usize, text: &str) -> Rectangle {
+        let comp = &self.circ().components[comp_i];
+        let size = self.rl.measure_text(text, self.settings.note_size) as f32;
+        let lines = text.lines().count().max(1) as f32;
+        Rectangle::new(comp.x, comp.y, size, self.settings.note_size as f32 * lines)
+    }
+    //the label or note text under world_pos
+    fn text_at(&self, world_pos: Vector2) -> Option<(usize, TextTarget)> {
+        let c = self.circ();
+        (0..c.components.len()).rev().find_map(|comp_i| {
+            let comp = &c.components[comp_i];
+            if comp.node_type == NodeType::NOTE {
+                let text = comp.text.as_deref().unwrap_or("");
+                if self.note_rect(comp_i, text).check_collision_point_rec(world_pos) {
+                    return Some((comp_i, TextTarget::Note));
+                }
+            }
+            //small labels are hard to hit, so give them some slack
+            let mut rect = self.label_rect(comp_i, &self.comp_labels[comp_i]);
+            rect.x -= 4.0;
+            rect.width = max(rect.width, 10.0) + 8.0;
+            rect.check_collision_point_rec(world_pos).then_some((comp_i, TextTarget::Label))
+        })
+    }
+    fn start_text_edit(&mut self, comp_i: usize, target: TextTarget) {
+        let comp = &self.circ().components[comp_i];
+        let text = match target {
+            TextTarget::Label => comp.label.clone(),
+            TextTarget::Note => comp.text.clone(),
+            TextTarget::X => Some(comp.x.to_string()),
+            TextTarget::Y => Some(comp.y.to_string()),
+        }
+        .unwrap_or_default();
+        let cursor = text.chars().count();
+        self.text_edit = Some(TextEdit { comp: comp_i, target, text, cursor });
+        self.rl.set_exit_key(None);
+    }
+    fn finish_text_edit(&mut self, keep: bool) {
+        self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+        let Some(edit) = self.text_edit.take() else { return };
+        if !keep {
+            return;
+        }
+        let before = Fields::of(&self.circ().components[edit.comp]);
+        let mut after = before.clone();
+        match edit.target {
+            TextTarget::Label => after.label = (!edit.text.is_empty()).then_some(edit.text),
+            TextTarget::Note => after.text = Some(edit.text),
+            TextTarget::X | TextTarget::Y => match edit.text.trim().parse::<f32>() {
+                Ok(v) if edit.target == TextTarget::X => after.x = v,
+                Ok(v) => after.y = v,
+                Err(_) => {
+                    self.set_status(format!("{} isn't a number", edit.text));
+                    return;
+                }
+            },
+        }
+        if after != before {
+            self.commit(ChangeKind::Replace(vec![(edit.comp, before, after)]));
+        }
+    }
+    fn update_text_edit(&mut self) {
+        let ctrl = self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+        let Some(edit) = self.text_edit.as_mut() else { return };
+        while let Some(c) = self.rl.get_char_pressed() {
+            edit.insert(c);
+        }
+        let len = edit.text.chars().count();
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            edit.backspace();
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_DELETE) {
+            edit.delete();
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+            edit.cursor = edit.cursor.saturating_sub(1);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+            edit.cursor = (edit.cursor + 1).min(len);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_HOME) {
+            edit.cursor = 0;
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_END) {
+            edit.cursor = len;
+        } else if edit.target == TextTarget::Note && ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.settings.note_size += 4;
+            theme::save_note_size(self.settings.note_size);
+        } else if edit.target == TextTarget::Note && ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.settings.note_size = (self.settings.note_size - 4).max(MIN_NOTE_SIZE);
+            theme::save_note_size(self.settings.note_size);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            //notes are multi-line, ctrl+enter finishes them
+            if edit.target == TextTarget::Note && !ctrl {
+                edit.insert('\n');
+            } else {
+                self.finish_text_edit(true);
+            }
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.finish_text_edit(false);
+        }
+    }
+    //(text, what clicking it does) for the selected component
+    fn inspector_rows(&self) -> Vec<(String, Option<InspectorAction>)> {
+        let [comp_i] = self.selected[..] else { return Vec::new() };
+        let comp = &self.circ().components[comp_i];
+        let editing = |target: TextTarget| {
+            self.text_edit
+                .as_ref()
+                .filter(|e| e.comp == comp_i && e.target == target)
+                .map(|e| format!("{}_", e.text))
+        };
+        let edit = |target| self.settings.edit_mode.then_some(InspectorAction::Edit(target));
+        let bits = |states: &[bool]| states.iter().map(|b| bool_str(*b)).collect::<String>();
+        let mut rows = vec![
+            (format!("id: {:?}", comp.get_id()), None),
+            (format!("type: {}", comp.node_type), None),
+            (
+                format!("label: {}", editing(TextTarget::Label).unwrap_or_else(|| comp.label.clone().unwrap_or_default())),
+                edit(TextTarget::Label),
+            ),
+            (format!("x: {}", editing(TextTarget::X).unwrap_or_else(|| comp.x.to_string())), edit(TextTarget::X)),
+            (format!("y: {}", editing(TextTarget::Y).unwrap_or_else(|| comp.y.to_string())), edit(TextTarget::Y)),
+            (format!("inputs: {}", sls::get_num_inputs(comp)), None),
+            (format!("outputs: {}", comp.outputs.len()), None),
+            (format!("input states: {}", bits(&comp.input_states)), None),
+        ];
+        let toggle = (self.settings.edit_mode && comp.node_type == NodeType::TOGGLE_BUTTON).then_some(InspectorAction::ToggleState);
+        rows.push((format!("output states: {}", bits(&comp.outputs)), toggle));
+        if comp.node_type == NodeType::NOTE {
+            rows.push((format!("text: {}", comp.text.as_deref().unwrap_or("").replace('\n', " / ")), edit(TextTarget::Note)));
+        }
+        if let Some(ic) = &comp.ic_instance {
+            rows.push((format!("ic: {} (open)", ic.header.name), Some(InspectorAction::EnterIc)));
+            rows.push((format!("has_dynamic: {}", ic.has_dynamic), None));
+        }
+        rows
+    }
+    fn inspector_rect(&self, rows: usize) -> Rectangle {
+        Rectangle::new(MINIMAP_MARGIN, INSPECTOR_TOP, INSPECTOR_W, rows as f32 * INSPECTOR_ROW + 8.0)
+    }
+    //returns true if the click landed on the inspector
+    fn update_inspector(&mut self, mouse_pos: Vector2) -> bool {
+        if !self.settings.show_inspector {
+            return false;
+        }
+        let rows = self.inspector_rows();
+        let rect = self.inspector_rect(rows.len());
+        if rows.is_empty() || !rect.check_collision_point_rec(mouse_pos) {
+            return false;
+        }
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let row = ((mouse_pos.y - rect.y - 4.0) / INSPECTOR_ROW) as usize;
+            let comp_i = self.selected[0];
+            match rows.get(row).and_then(|(_, action)| *action) {
+                Some(InspectorAction::Edit(target)) => {
+                    self.finish_text_edit(true);
+                    self.start_text_edit(comp_i, target);
+                }
+                Some(InspectorAction::ToggleState) => self.toggle_initial_state(),
+                Some(InspectorAction::EnterIc) => self.enter_ic(comp_i),
+                None => {}
+            }
+        }
+        true
+    }
+    fn data_path(&self) -> String {
+        data_path(self.circuit_path.as_deref())
+    }
+    //the built in circuit has nowhere to go yet, so that asks for a path first
+    fn save_circuit(&mut self) {
+        match self.circuit_path.clone() {
+            Some(path) => self.save_circuit_as(path),
+            None => {
+                self.save_prompt = Some(self.data_path());
+                self.rl.set_exit_key(None);
+            }
+        }
+    }
+    fn save_circuit_as(&mut self, path: String) {
+        let text = match serde_json::to_string_pretty(&self.circuit) {
+            Ok(text) => text,
+            Err(e) => {
+                self.set_status(format!("couldn't serialize the circuit: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = Path::new(&path).parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, text)) {
+            self.set_status(format!("couldn't save {}: {}", path, e));
+            return;
+        }
+        if self.circuit_path.as_ref() != Some(&path) {
+            //views and bus rules move along to the new sidecars
+            self.circuit_path = Some(path.clone());
+            if let Err(e) = bookmarks::save(&path, &self.bookmarks).and_then(|_| buses::save(&path, &self.bus_rules)) {
+                self.set_status(format!("saved {}, but {}", path, e));
+                return;
+            }
+        }
+        self.set_status(format!("saved {}", path));
+    }
+    fn update_save_prompt(&mut self) {
+        let Some(path) = self.save_prompt.as_mut() else { return };
+        while let Some(c) = self.rl.get_char_pressed() {
+            path.push(c);
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            path.pop();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            let path = std::mem::take(path);
+            self.save_prompt = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            if !path.trim().is_empty() {
+                self.save_circuit_as(path.trim().to_string());
+            }
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.save_prompt = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+        }
+    }
+    fn update_export_prompt(&mut self) {
+        let Some((whole, scale)) = self.export_prompt.as_mut() else { return };
+        while let Some(c) = self.rl.get_char_pressed() {
+            if c.is_ascii_digit() || c == '.' {
+                scale.push(c);
+            }
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            scale.pop();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            let (whole, scale) = (*whole, std::mem::take(scale));
+            self.export_prompt = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            match scale.parse::<f32>() {
+                Ok(scale) if scale > 0.0 => self.queue_export(whole, scale),
+                _ => self.set_status(format!("bad scale {:?}", scale)),
+            }
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.export_prompt = None;
+            self.rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+        }
+    }
+    //the whole circuit at scale pixels per world unit, or the view at scale times its size on screen
+    fn queue_export(&mut self, whole: bool, scale: f32) {
+        let (area, zoom) = if whole {
+            let Some(rect) = bounds_of(self.circ().components.iter()) else {
+                self.set_status("nothing to export".to_string());
+                return;
+            };
+            let area = Rectangle::new(
+                rect.x - EXPORT_MARGIN,
+                rect.y - EXPORT_MARGIN,
+                rect.width + EXPORT_MARGIN * 2.0,
+                rect.height + EXPORT_MARGIN * 2.0,
+            );
+            (area, scale)
+        } else {
+            let corner = Vector2::new(self.rl.get_render_width() as f32, self.rl.get_render_height() as f32);
+            let tl = self.rl.get_screen_to_world2D(Vector2::zero(), self.cam);
+            let br = self.rl.get_screen_to_world2D(corner, self.cam);
+            (Rectangle::new(tl.x, tl.y, br.x - tl.x, br.y - tl.y), self.cam.zoom * scale)
+        };
+        let zoom = zoom.min(MAX_EXPORT_PIXELS / area.width.max(area.height));
+        let path = Path::new(&self.data_path()).with_extension(if whole { "png" } else { "view.png" });
+        self.pending_export = Some(PngExport { area, zoom, path });
+    }
+    //renders into an offscreen texture with the live signal colors, without the editing feedback
+    fn export_png(&mut self, export: PngExport) {
+        let width = (export.area.width * export.zoom).ceil() as u32;
+        let height = (export.area.height * export.zoom).ceil() as u32;
+        let mut target = match self.rl.load_render_texture(&self.t, width, height) {
+            Ok(target) => target,
+            Err(e) => {
+                self.set_status(format!("couldn't export: {}", e));
+                return;
+            }
+        };
+        let cam = Camera2D {
+            offset: Vector2::zero(),
+            target: Vector2::new(export.area.x, export.area.y),
+            rotation: 0.0,
+            zoom: export.zoom,
+        };
+        let theme = self.settings.theme.colors();
+        let world = World {
+            circuit: circuit_at(&self.circuit, &self.ic_path),
+            settings: &self.settings,
+            comp_labels: &self.comp_labels,
+            in_pin_pos: &self.in_pin_pos,
+            out_pin_pos: &self.out_pin_pos,
+            comp_inputs: &self.comp_inputs,
+            comp_outputs: &self.comp_outputs,
+            ic_pin_names: &self.ic_pin_names,
+            buses: &self.buses,
+            expanded_buses: &self.expanded_buses,
+            highlight: self.highlight,
+            text_edit: self.text_edit.as_ref(),
+        };
+        {
+            let mut tex = self.rl.begin_texture_mode(&self.t, &mut target);
+            tex.clear_background(theme.background);
+            let mut draw = tex.begin_mode2D(cam);
+            draw_world(&mut draw, &world, None, cam, export.area, theme);
+        }
+        match target.texture().load_image() {
+            Ok(mut image) => {
+                //render textures come out upside down
+                image.flip_vertical();
+                let path = export.path.to_string_lossy();
+                if image.export_image(&path) {
+                    self.set_status(format!("exported {}x{} to {}", width, height, path));
+                } else {
+                    self.set_status(format!("couldn't write {}", path));
+                }
+            }
+            Err(e) => self.set_status(format!("couldn't export: {}", e)),
+        }
+    }
+    //pins first since they sit on top of the component edges
+    fn hit_test(&self, world_pos: Vector2) -> Option<Hover> {
+        for (comp_i, pins) in self.in_pin_pos.iter().enumerate() {
+            for (pin_i, pin) in pins.iter().enumerate() {
+                if pin.distance_to(world_pos) <= PIN_SIZE {
+                    return Some(Hover::InPin(comp_i, pin_i));
+                }
+            }
+        }
+        for (comp_i, pins) in self.out_pin_pos.iter().enumerate() {
+            if self.circ().components[comp_i].node_type == NodeType::LIGHT_BULB {
+                continue;
+            }
+            for (pin_i, pin) in pins.iter().enumerate() {
+                if pin.distance_to(world_pos) <= PIN_SIZE {
+                    return Some(Hover::OutPin(comp_i, pin_i));
+                }
+            }
+        }
+        //last drawn is on top
+        self.circ()
+            .components
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, comp)| get_comp_rect(comp).check_collision_point_rec(world_pos))
+            .map(|(comp_i, _)| Hover::Comp(comp_i))
+    }
+    fn update_hover(&mut self, mouse_pos: Vector2) {
+        //on touch the pointer only counts once it's a long press
+        let pointer_ok = if self.rl.get_touch_point_count() > 0 {
+            self.rl.is_gesture_detected(Gesture::GESTURE_HOLD)
+        } else {
+            self.drag_start.is_none()
+        };
+        self.hover = if pointer_ok {
+            let world_pos = self.rl.get_screen_to_world2D(mouse_pos, self.cam);
+            self.hit_test(world_pos)
+        } else {
+            None
+        };
+    }
+    fn tooltip_lines(&self, hover: Hover) -> Vec<String> {
+        let comp_i = match hover {
+            Hover::Comp(i) | Hover::InPin(i, _) | Hover::OutPin(i, _) => i,
+        };
+        let comp = &self.circ().components[comp_i];
+        let mut lines = vec![
+            self.label_of(comp_i),
+            format!("type: {}", comp.node_type),
+            format!("id: {:?}", comp.get_id()),
+        ];
+        match hover {
+            Hover::Comp(_) => {
+                let ins: String = comp.input_states.iter().map(|b| bool_str(*b)).collect();
+                let outs: String = comp.outputs.iter().map(|b| bool_str(*b)).collect();
+                lines.push(format!("inputs: {} [{}]", sls::get_num_inputs(comp), ins));
+                lines.push(format!("outputs: {} [{}]", comp.outputs.len(), outs));
+            }
+            Hover::InPin(_, pin) => {
+                let value = comp.input_states.get(pin).copied().unwrap_or(false);
+                lines.push(format!("input pin {}{}: {}", pin, self.pin_name(comp_i, pin, true), bool_str(value)));
+                match self.comp_inputs[comp_i].iter().find(|input| input.in_pin == pin) {
+                    Some(input) => lines.push(format!(
+                        "from: {} out {}",
+                        self.label_of(input.other_comp),
+                        input.other_pin
+                    )),
+                    None => lines.push("from: nothing".to_string()),
+                }
+            }
+            Hover::OutPin(_, pin) => {
+                let value = comp.outputs.get(pin).copied().unwrap_or(false);
+                lines.push(format!("output pin {}{}: {}", pin, self.pin_name(comp_i, pin, false), bool_str(value)));
+                let mut any = false;
+                for output in self.comp_outputs[comp_i].iter().filter(|output| output.out_pin == pin) {
+                    lines.push(format!("to: {} in {}", self.label_of(output.other_comp), output.other_pin));
+                    any = true;
+                }
+                if !any {
+                    lines.push("to: nothing".to_string());
+                }
+            }
+        }
+        lines
+    }
+    //" (name)" for IC pins whose button or bulb has a label, empty otherwise
+    fn pin_name(&self, comp_i: usize, pin: usize, input: bool) -> String {
+        let (ins, outs) = &self.ic_pin_names[comp_i];
+        match if input { ins.get(pin) } else { outs.get(pin) } {
+            Some(name) if !name.is_empty() => format!(" ({})", name),
+            _ => String::new(),
+        }
+    }
+    //finds the net under world_pos, through a pin or a wire
+    fn net_at(&self, world_pos: Vector2) -> Option<Net> {
+        match self.hit_test(world_pos) {
+            Some(Hover::OutPin(comp, pin)) => return Some(Net { comp, pin }),
+            Some(Hover::InPin(comp_i, pin)) => {
+                return self.comp_inputs[comp_i]
+                    .iter()
+                    .find(|input| input.in_pin == pin)
+                    .map(|input| Net { comp: input.other_comp, pin: input.other_pin });
+            }
+            _ => {}
+        }
+        self.wire_at(world_pos).map(|(comp_i, pin)| {
+            let input = self.comp_inputs[comp_i].iter().find(|input| input.in_pin == pin).unwrap();
+            Net { comp: input.other_comp, pin: input.other_pin }
+        })
+    }
+    //(component, input pin) of the wire under world_pos
+    fn wire_at(&self, world_pos: Vector2) -> Option<(usize, usize)> {
+        //keep wires clickable when zoomed out
+        let reach = max(WIRE_THICKNES, 4.0 / self.cam.zoom);
+        for (comp_i, inputs) in self.comp_inputs.iter().enumerate() {
+            for input in inputs.iter().filter(|input| !self.bus_collapsed(input.bus)) {
+                if distance_to_path(world_pos, &input.route) <= reach {
+                    return Some((comp_i, input.in_pin));
+                }
+            }
+        }
+        None
+    }
+    fn bus_collapsed(&self, bus: Option<usize>) -> bool {
+        bus_collapsed(&self.buses, &self.expanded_buses, bus)
+    }
+    fn bus_route(&self, bus_i: usize) -> &[Vector2] {
+        bus_route(&self.buses[bus_i], &self.comp_inputs)
+    }
+    //a collapsed bus's line or an expanded one's label under world_pos
+    fn bus_at(&self, world_pos: Vector2) -> Option<usize> {
+        let reach = max(BUS_THICKNES, 4.0 / self.cam.zoom);
+        (0..self.buses.len()).find(|&bus_i| {
+            let route = self.bus_route(bus_i);
+            if self.bus_collapsed(Some(bus_i)) {
+                distance_to_path(world_pos, route) <= reach
+            } else {
+                bus_anchor(route).distance_to(world_pos) <= max(BUS_LABEL_SIZE as f32, reach)
+            }
+        })
+    }
+    fn toggle_bus(&mut self, bus_i: usize) {
+        let pair = (self.buses[bus_i].src, self.buses[bus_i].sink);
+        match self.expanded_buses.iter().position(|p| *p == pair) {
+            Some(pos) => {
+                self.expanded_buses.remove(pos);
+            }
+            None => self.expanded_buses.push(pair),
+        }
+    }
+    //groups (or splits) the wires between the two selected components, remembered in the sidecar
+    fn group_bus(&mut self, grouped: bool) {
+        let [a, b] = self.selected[..] else {
+            self.set_status("select the two components the bus runs between".to_string());
+            return;
+        };
+        let (src, sink) = if self.comp_inputs[b].iter().any(|input| input.other_comp == a) {
+            (a, b)
+        } else if self.comp_inputs[a].iter().any(|input| input.other_comp == b) {
+            (b, a)
+        } else {
+            self.set_status("no wires between those two".to_string());
+            return;
+        };
+        let ic_path = self.ic_path_ids();
+        let c = self.circ();
+        let (src_id, sink_id) = (c.components[src].get_id().clone(), c.components[sink].get_id().clone());
+        self.bus_rules.retain(|rule| !(rule.ic_path == ic_path && rule.src == src_id && rule.sink == sink_id));
+        self.bus_rules.push(BusRule { ic_path, src: src_id, sink: sink_id, grouped });
+        let saved = buses::save(&self.data_path(), &self.bus_rules);
+        self.rebuild_caches();
+        let msg = if grouped { "grouped into a bus" } else { "split into wires" };
+        match saved {
+            Ok(()) => self.set_status(format!("{} -> {} {}", self.label_of(src), self.label_of(sink), msg)),
+            Err(e) => self.set_status(e),
+        }
+    }
+    fn set_status(&mut self, msg: String) {
+        self.status = Some((msg, Instant::now()));
+    }
     fn update_zoom(&mut self,mouse_pos:Vector2) {
         let rl: &mut RaylibHandle = &mut self.rl;
         let scroll = rl.get_mouse_wheel_move();
@@ -196,7 +1728,7 @@ impl State {
 
             // uses log scaling to provide consistent zoom
             let scale = 0.2 * scroll;
-            self.cam.zoom = (self.cam.zoom.ln() + scale).exp().clamp(0.125, 64.0);
+            self.cam.zoom = (self.cam.zoom.ln() + scale).exp().clamp(MIN_ZOOM, MAX_ZOOM);
         }
         if rl.get_touch_point_count()>=2 {
             let p1 = rl.get_touch_position(0);
@@ -228,10 +1760,12 @@ impl State {
     }
     fn update_drag(&mut self,mouse_pos:Vector2) {
         let rl = &mut self.rl;
-        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+        //in edit mode left drag selects, so pan with the right button
+        let button = if self.settings.edit_mode { MouseButton::MOUSE_BUTTON_RIGHT } else { MouseButton::MOUSE_BUTTON_LEFT };
+        if rl.is_mouse_button_pressed(button) {
             self.drag_start=Some(mouse_pos);
         }
-        if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+        if rl.is_mouse_button_released(button) {
             self.drag_start=None;
         }
         if let Some(drag) = self.drag_start.as_mut() {
@@ -240,6 +1774,493 @@ impl State {
             *drag = mouse_pos;
         }
     }
+    fn copy_selection(&mut self) -> Option<String> {
+        if self.selected.is_empty() {
+            return None;
+        }
+        match edit::copy_fragment(self.circ(), &self.selected) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                self.set_status(format!("couldn't copy: {}", e));
+                None
+            }
+        }
+    }
+    fn copy(&mut self) {
+        let Some(text) = self.copy_selection() else { return };
+        if self.rl.set_clipboard_text(&text).is_err() {
+            println!("couldn't set the system clipboard, keeping it to ourselves");
+        }
+        self.set_status(format!("copied {} components", self.selected.len()));
+        self.clipboard = Some(text);
+    }
+    fn paste(&mut self) {
+        let text = match self.rl.get_clipboard_text() {
+            Ok(text) if !text.is_empty() => text,
+            _ => match &self.clipboard {
+                Some(text) => text.clone(),
+                None => return,
+            },
+        };
+        let world_pos = self.rl.get_screen_to_world2D(self.rl.get_mouse_position(), self.cam);
+        self.paste_at(&text, world_pos);
+    }
+    fn duplicate(&mut self) {
+        let Some(text) = self.copy_selection() else { return };
+        let c = self.circ();
+        let Some(bounds) = bounds_of(self.selected.iter().map(|&comp_i| &c.components[comp_i])) else { return };
+        self.paste_at(&text, Vector2::new(bounds.x + DUPLICATE_OFFSET, bounds.y + DUPLICATE_OFFSET));
+    }
+    //inserts a fragment with its top left corner at world_pos and selects it
+    fn paste_at(&mut self, text: &str, world_pos: Vector2) {
+        let mut ids = IdGen::new(self.circ());
+        let mut comps = match edit::paste_fragment(text, &mut ids) {
+            Ok(comps) if !comps.is_empty() => comps,
+            Ok(_) => return,
+            Err(e) => {
+                self.set_status(format!("couldn't paste: {}", e));
+                return;
+            }
+        };
+        let Some(bounds) = bounds_of(comps.iter()) else { return };
+        let mut delta = world_pos - Vector2::new(bounds.x, bounds.y);
+        if self.settings.snap_to_grid {
+            let step = grid_step(self.cam.zoom);
+            delta = Vector2::new(snap_to_grid(delta.x, step), snap_to_grid(delta.y, step));
+        }
+        for comp in &mut comps {
+            comp.x += delta.x;
+            comp.y += delta.y;
+        }
+        let first = self.circ().components.len();
+        let count = comps.len();
+        self.commit(ChangeKind::Insert(comps.into_iter().enumerate().map(|(i, comp)| (first + i, comp)).collect()));
+        self.selected = (first..first + count).collect();
+    }
+    //swaps the selection for an IC holding it, and saves that IC to the library so it can be placed again
+    fn make_ic_from_selection(&mut self) {
+        if self.selected.is_empty() {
+            return;
+        }
+        let mut comps = self.selected.clone();
+        comps.sort_unstable();
+        let data = self.data_path();
+        let library = library_dir(&data);
+        let name = (1..)
+            .map(|n| format!("ic-{}", n))
+            .find(|name| !library.join(format!("{}.slj", name)).exists())
+            .unwrap();
+        let c = self.circ();
+        let extracted = match edit::extract_ic(c, &comps, &name) {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                self.set_status(format!("couldn't make an IC: {}", e));
+                return;
+            }
+        };
+        let path = library.join(format!("{}.slj", name));
+        //goes into the library once the IC is in the circuit
+        let text = match serde_json::to_string(&extracted.ic) {
+            Ok(text) => text,
+            Err(e) => {
+                self.set_status(format!("couldn't make an IC: {}", e));
+                return;
+            }
+        };
+        let bounds = bounds_of(comps.iter().map(|&comp_i| &c.components[comp_i])).unwrap();
+        let ic_id = IdGen::new(c).next();
+        let mut ic_comp = match edit::new_ic(extracted.ic, bounds.x, bounds.y, ic_id.clone()) {
+            Ok(comp) => comp,
+            Err(e) => {
+                self.set_status(format!("couldn't make an IC: {}", e));
+                return;
+            }
+        };
+        for (pin, &(src, src_pin)) in extracted.inputs.iter().enumerate() {
+            match serde_json::from_value(edit::input_json(pin, src_pin, c.components[src].get_id())) {
+                Ok(input) => ic_comp.inputs.push(input),
+                Err(e) => println!("couldn't wire IC input {}: {}", pin, e),
+            }
+        }
+        //whatever the selection drove now hangs off the IC's outputs
+        let ids: Vec<&ID> = comps.iter().map(|&comp_i| c.components[comp_i].get_id()).collect();
+        let mut rewired = Vec::new();
+        for (comp_i, comp) in c.components.iter().enumerate() {
+            if comps.binary_search(&comp_i).is_ok() || !comp.inputs.iter().any(|input| ids.contains(&&input.other_id)) {
+                continue;
+            }
+            let before = Fields::of(comp);
+            let mut after = before.clone();
+            for (_, other_pin, other_id) in &mut after.inputs {
+                let Some(src) = c.components.iter().position(|other| other.get_id() == other_id) else { continue };
+                if let Some(pin) = extracted.outputs.iter().position(|&o| o == (src, *other_pin)) {
+                    *other_id = ic_id.clone();
+                    *other_pin = pin;
+                }
+            }
+            rewired.push((comp_i, before, after));
+        }
+        let removed: Vec<_> = comps.iter().map(|&comp_i| (comp_i, c.components[comp_i].clone())).collect();
+        let ic_i = c.components.len() - removed.len();
+        self.commit(ChangeKind::Batch(vec![
+            ChangeKind::Replace(rewired),
+            ChangeKind::Remove(removed),
+            ChangeKind::Insert(vec![(ic_i, ic_comp)]),
+        ]));
+        self.selected = vec![ic_i];
+        match std::fs::create_dir_all(library).and_then(|_| std::fs::write(&path, text)) {
+            Ok(()) => {
+                self.palette.push(PaletteItem::Ic(name.clone(), path));
+                self.set_status(format!("made {} from {} components", name, comps.len()));
+            }
+            Err(e) => self.set_status(format!("made {}, but couldn't add it to the library: {}", name, e)),
+        }
+    }
+    //moves components to new corners as one undoable edit
+    fn move_comps(&mut self, positions: Vec<(usize, f32, f32)>) {
+        let c = self.circ();
+        let moved: Vec<_> = positions
+            .into_iter()
+            .filter(|&(comp_i, x, y)| (c.components[comp_i].x, c.components[comp_i].y) != (x, y))
+            .map(|(comp_i, x, y)| {
+                let before = Fields::of(&c.components[comp_i]);
+                let mut after = before.clone();
+                after.x = x;
+                after.y = y;
+                (comp_i, before, after)
+            })
+            .collect();
+        if !moved.is_empty() {
+            self.commit(ChangeKind::Replace(moved));
+        }
+    }
+    fn arrange_selection(&mut self, op: Arrange) {
+        let c = self.circ();
+        let rects: Vec<(usize, Rectangle)> = self.selected.iter().map(|&comp_i| (comp_i, get_comp_rect(&c.components[comp_i]))).collect();
+        if rects.len() < 2 {
+            self.set_status("select at least two components".to_string());
+            return;
+        }
+        self.move_comps(arrange(op, &rects));
+    }
+    //slides each selected component up or down so its first wired input lines up with what drives it,
+    //left to right so a chain straightens out in one go
+    fn stack_pins(&mut self) {
+        let mut order = self.selected.clone();
+        let c = self.circ();
+        order.sort_by(|a, b| c.components[*a].x.total_cmp(&c.components[*b].x));
+        let before: Vec<(usize, Vector2)> = order.iter().map(|&i| (i, Vector2::new(c.components[i].x, c.components[i].y))).collect();
+        for &comp_i in &order {
+            let Some(input) = self.comp_inputs[comp_i].iter().min_by_key(|input| input.in_pin) else { continue };
+            let dy = self.out_pin_pos[input.other_comp][input.other_pin].y - self.in_pin_pos[comp_i][input.in_pin].y;
+            let comp = &self.circ().components[comp_i];
+            let (x, y) = (comp.x, comp.y + dy);
+            //live so the next one in the chain sees where this one ended up
+            self.move_comp(comp_i, x, y);
+        }
+        let positions: Vec<(usize, f32, f32)> = before
+            .iter()
+            .map(|&(comp_i, _)| (comp_i, self.circ().components[comp_i].x, self.circ().components[comp_i].y))
+            .collect();
+        for &(comp_i, pos) in &before {
+            self.move_comp(comp_i, pos.x, pos.y);
+        }
+        self.move_comps(positions);
+    }
+    //selects every component touching the box, adding to the selection with shift
+    fn finish_box_select(&mut self, start: Vector2, end: Vector2, add: bool) {
+        let rect = Rectangle::new(start.x.min(end.x), start.y.min(end.y), (start.x - end.x).abs(), (start.y - end.y).abs());
+        if !add {
+            self.selected.clear();
+        }
+        for (comp_i, comp) in circuit_at(&self.circuit, &self.ic_path).components.iter().enumerate() {
+            if get_comp_rect(comp).check_collision_recs(&rect) && !self.selected.contains(&comp_i) {
+                self.selected.push(comp_i);
+            }
+        }
+    }
+    //updates a component's position and the pins that hang off it
+    fn move_comp(&mut self, comp_i: usize, x: f32, y: f32) {
+        let comp = &mut self.circ_mut().components[comp_i];
+        comp.x = x;
+        comp.y = y;
+        let (in_pin, out_pin) = calculate_pin_pos(comp);
+        self.in_pin_pos[comp_i] = in_pin;
+        self.out_pin_pos[comp_i] = out_pin;
+        self.reroute(comp_i);
+    }
+    //routes again every wire into or out of comp_i, others are left until the next rebuild_caches
+    fn reroute(&mut self, comp_i: usize) {
+        let rects: Vec<Rectangle> = circuit_at(&self.circuit, &self.ic_path).components.iter().map(get_comp_rect).collect();
+        let orthogonal = self.settings.orthogonal_wires;
+        for input in &mut self.comp_inputs[comp_i] {
+            let from = self.out_pin_pos[input.other_comp][input.other_pin];
+            let to = self.in_pin_pos[comp_i][input.in_pin];
+            input.route = wire_route(orthogonal, &rects, input.other_comp, comp_i, from, to);
+        }
+        for k in 0..self.comp_outputs[comp_i].len() {
+            let (out_pin, sink, in_pin) = {
+                let output = &self.comp_outputs[comp_i][k];
+                (output.out_pin, output.other_comp, output.other_pin)
+            };
+            let from = self.out_pin_pos[comp_i][out_pin];
+            let to = self.in_pin_pos[sink][in_pin];
+            let route = wire_route(orthogonal, &rects, comp_i, sink, from, to);
+            if let Some(input) = self.comp_inputs[sink].iter_mut().find(|input| input.in_pin == in_pin) {
+                input.route = route;
+            }
+        }
+    }
+    fn palette_rect(&self) -> Rectangle {
+        let w = self.rl.get_render_width() as f32;
+        let h = self.rl.get_render_height() as f32;
+        Rectangle::new(w - PALETTE_W, PALETTE_TOP, PALETTE_W, h - PALETTE_TOP)
+    }
+    fn palette_item_at(&self, mouse_pos: Vector2) -> Option<usize> {
+        let rect = self.palette_rect();
+        if !rect.check_collision_point_rec(mouse_pos) {
+            return None;
+        }
+        let row = ((mouse_pos.y - rect.y + self.palette_scroll) / PALETTE_ROW) as usize;
+        (row < self.palette.len()).then_some(row)
+    }
+    //returns true while the palette owns the pointer
+    fn update_palette(&mut self, mouse_pos: Vector2) -> bool {
+        let over = self.palette_rect().check_collision_point_rec(mouse_pos);
+        if over {
+            let max_scroll = max(self.palette.len() as f32 * PALETTE_ROW - self.palette_rect().height, 0.0);
+            self.palette_scroll = (self.palette_scroll - self.rl.get_mouse_wheel_move() * PALETTE_ROW).clamp(0.0, max_scroll);
+        }
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.placing = self.palette_item_at(mouse_pos);
+        }
+        if self.rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            if let Some(item) = self.placing.take() {
+                if !over {
+                    let world_pos = self.rl.get_screen_to_world2D(mouse_pos, self.cam);
+                    self.place(item, world_pos);
+                }
+                return true;
+            }
+        }
+        over || self.placing.is_some()
+    }
+    fn place(&mut self, item: usize, world_pos: Vector2) {
+        let (mut x, mut y) = (world_pos.x, world_pos.y);
+        if self.settings.snap_to_grid {
+            let step = grid_step(self.cam.zoom);
+            (x, y) = (snap_to_grid(x, step), snap_to_grid(y, step));
+        }
+        let id = IdGen::new(self.circ()).next();
+        let comp = match &self.palette[item] {
+            PaletteItem::Node(node_type) => edit::new_component(*node_type, x, y, id).map_err(|e| e.to_string()),
+            PaletteItem::Ic(_, path) => edit::load_ic(path).and_then(|ic| edit::new_ic(ic, x, y, id).map_err(|e| e.to_string())),
+        };
+        match comp {
+            Ok(comp) => {
+                let comp_i = self.circ().components.len();
+                self.commit(ChangeKind::Insert(vec![(comp_i, comp)]));
+                self.selected = vec![comp_i];
+            }
+            Err(e) => self.set_status(format!("couldn't place {}: {}", self.palette[item].name(), e)),
+        }
+    }
+    //applies an edit to circ() and records it for undo
+    fn commit(&mut self, kind: ChangeKind) {
+        kind.apply(self.circ_mut());
+        self.record(kind);
+    }
+    //records an edit that's already been applied
+    fn record(&mut self, kind: ChangeKind) {
+        self.after_change(kind.shifts_indexes(), kind.changes_wiring());
+        let ic_path = self.ic_path_ids();
+        self.history.push(Change { ic_path, kind });
+    }
+    //drops everything that holds component indexes of the circuit on screen
+    fn forget_indexes(&mut self) {
+        self.selected.clear();
+        self.highlight = None;
+        self.selected_wire = None;
+        self.wiring = None;
+        self.moving = None;
+    }
+    fn after_change(&mut self, shifts_indexes: bool, changes_wiring: bool) {
+        if shifts_indexes {
+            self.forget_indexes();
+        }
+        if changes_wiring {
+            self.resync();
+        } else {
+            self.rebuild_caches();
+        }
+    }
+    fn undo(&mut self) {
+        let here = self.ic_path_ids();
+        let Some(ids) = self.history.undo_ic_path() else {
+            self.set_status("nothing to undo".to_string());
+            return;
+        };
+        //left where it is if it can't be applied, so it doesn't get applied twice later
+        let Some(ic_path) = resolve_ic_path(&self.circuit, ids) else {
+            self.set_status("the IC that edit was in is gone".to_string());
+            return;
+        };
+        let Some(change) = self.history.undo() else { return };
+        change.kind.apply(circuit_at_mut(&mut self.circuit, &ic_path));
+        let (shifts, wiring) = (change.kind.shifts_indexes(), change.kind.changes_wiring());
+        self.follow_ic_path(&here);
+        self.after_change(shifts, wiring);
+    }
+    fn redo(&mut self) {
+        let here = self.ic_path_ids();
+        let Some(ids) = self.history.redo_ic_path() else {
+            self.set_status("nothing to redo".to_string());
+            return;
+        };
+        let Some(ic_path) = resolve_ic_path(&self.circuit, ids) else {
+            self.set_status("the IC that edit was in is gone".to_string());
+            return;
+        };
+        let Some(change) = self.history.redo() else { return };
+        change.kind.apply(circuit_at_mut(&mut self.circuit, &ic_path));
+        let (shifts, wiring) = (change.kind.shifts_indexes(), change.kind.changes_wiring());
+        self.follow_ic_path(&here);
+        self.after_change(shifts, wiring);
+    }
+    //stays in the same IC after an undo or redo moved it around, or goes back to the top if it's gone
+    fn follow_ic_path(&mut self, ids: &[ID]) {
+        match resolve_ic_path(&self.circuit, ids) {
+            Some(ic_path) if ic_path == self.ic_path => {}
+            Some(ic_path) => {
+                self.leave_circuit();
+                self.ic_path = ic_path;
+                self.forget_indexes();
+            }
+            None => {
+                self.leave_circuit();
+                self.ic_path.clear();
+                self.forget_indexes();
+                self.set_status("the IC you were in is gone".to_string());
+            }
+        }
+    }
+    fn connect(&mut self, from: Net, comp_i: usize, in_pin: usize) {
+        if self.comp_inputs[comp_i].iter().any(|input| input.in_pin == in_pin) {
+            self.set_status(format!("input {} of {} is already driven", in_pin, self.label_of(comp_i)));
+            return;
+        }
+        let other_id = self.circ().components[from.comp].get_id().clone();
+        let before = Fields::of(&self.circ().components[comp_i]);
+        let mut after = before.clone();
+        after.inputs.push((in_pin, from.pin, other_id));
+        self.commit(ChangeKind::Replace(vec![(comp_i, before, after)]));
+    }
+    fn disconnect(&mut self, comp_i: usize, in_pin: usize) {
+        let before = Fields::of(&self.circ().components[comp_i]);
+        let mut after = before.clone();
+        after.inputs.retain(|input| input.0 != in_pin);
+        self.commit(ChangeKind::Replace(vec![(comp_i, before, after)]));
+    }
+    //removes the components and every wire they drive
+    fn delete_comps(&mut self, mut comps: Vec<usize>) {
+        comps.sort_unstable();
+        comps.dedup();
+        let c = self.circ();
+        let ids: Vec<&ID> = comps.iter().map(|&comp_i| c.components[comp_i].get_id()).collect();
+        let mut unwired = Vec::new();
+        for (comp_i, comp) in c.components.iter().enumerate() {
+            if comps.binary_search(&comp_i).is_ok() || !comp.inputs.iter().any(|input| ids.contains(&&input.other_id)) {
+                continue;
+            }
+            let before = Fields::of(comp);
+            let mut after = before.clone();
+            after.inputs.retain(|input| !ids.contains(&&input.2));
+            unwired.push((comp_i, before, after));
+        }
+        let removed = comps.iter().map(|&comp_i| (comp_i, c.components[comp_i].clone())).collect();
+        self.commit(ChangeKind::Batch(vec![ChangeKind::Replace(unwired), ChangeKind::Remove(removed)]));
+    }
+    //flips the stored state of toggle buttons, which is what the circuit starts with when saved
+    fn toggle_initial_state(&mut self) {
+        let c = self.circ();
+        let toggled: Vec<_> = self
+            .selected
+            .iter()
+            .filter(|&&comp_i| c.components[comp_i].node_type == NodeType::TOGGLE_BUTTON)
+            .map(|&comp_i| {
+                let before = Fields::of(&c.components[comp_i]);
+                let mut after = before.clone();
+                after.output = before.output.map(|b| !b);
+                (comp_i, before, after)
+            })
+            .collect();
+        if !toggled.is_empty() {
+            self.commit(ChangeKind::Replace(toggled));
+        }
+    }
+    //moving components and drawing wires
+    fn update_edit(&mut self, mouse_pos: Vector2) {
+        let world_pos = self.rl.get_screen_to_world2D(mouse_pos, self.cam);
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            match self.hit_test(world_pos) {
+                Some(Hover::Comp(comp_i)) => {
+                    let c = self.circ();
+                    let comp = &c.components[comp_i];
+                    let grab_offset = world_pos - Vector2::new(comp.x, comp.y);
+                    //dragging a selected component takes the rest of the selection along
+                    let comps = if self.selected.contains(&comp_i) { self.selected.clone() } else { vec![comp_i] };
+                    let before = comps.into_iter().map(|i| (i, Fields::of(&c.components[i]))).collect();
+                    self.moving = Some(Moving { anchor: comp_i, grab_offset, before });
+                }
+                Some(Hover::OutPin(comp, pin)) => self.wiring = Some(Net { comp, pin }),
+                Some(Hover::InPin(..)) => {}
+                None => {
+                    self.selected_wire = self.wire_at(world_pos);
+                    if self.selected_wire.is_none() {
+                        self.box_select = Some(world_pos);
+                    }
+                }
+            }
+        }
+        if self.rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            if let Some(moving) = self.moving.take() {
+                let c = self.circ();
+                let moved: Vec<_> = moving
+                    .before
+                    .into_iter()
+                    .filter(|(i, before)| (c.components[*i].x, c.components[*i].y) != (before.x, before.y))
+                    .map(|(i, before)| (i, before, Fields::of(&c.components[i])))
+                    .collect();
+                if !moved.is_empty() {
+                    self.record(ChangeKind::Replace(moved));
+                }
+            }
+            if let Some(start) = self.box_select.take() {
+                let shift = self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+                self.finish_box_select(start, world_pos, shift);
+            }
+            if let Some(from) = self.wiring.take() {
+                match self.hit_test(world_pos) {
+                    Some(Hover::InPin(comp_i, in_pin)) => self.connect(from, comp_i, in_pin),
+                    Some(Hover::OutPin(..)) => self.set_status("wires go from an output to an input".to_string()),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(moving) = &self.moving {
+            let mut pos = world_pos - moving.grab_offset;
+            if self.settings.snap_to_grid {
+                let step = grid_step(self.cam.zoom);
+                pos = Vector2::new(snap_to_grid(pos.x, step), snap_to_grid(pos.y, step));
+            }
+            let anchor = &moving.before.iter().find(|(i, _)| *i == moving.anchor).unwrap().1;
+            let delta = pos - Vector2::new(anchor.x, anchor.y);
+            let targets: Vec<(usize, f32, f32)> = moving.before.iter().map(|(i, b)| (*i, b.x + delta.x, b.y + delta.y)).collect();
+            for (comp_i, x, y) in targets {
+                self.move_comp(comp_i, x, y);
+            }
+        }
+    }
     pub fn update(&mut self) {
         let dur = Duration::from_secs_f64(1./(30.));
         let mut count:usize=0;
@@ -252,7 +2273,123 @@ impl State {
         self.run_times=count;
         self.begin=Instant::now();
 
-        if self.rl.is_key_pressed(KeyboardKey::KEY_F) {
+        let ctrl = self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+        let shift = self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        let alt = self.rl.is_key_down(KeyboardKey::KEY_LEFT_ALT) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT);
+        //anything being typed holds component indexes or owns the keyboard, so history waits until it's done
+        let typing = self.search.is_some()
+            || self.naming.is_some()
+            || self.export_prompt.is_some()
+            || self.save_prompt.is_some()
+            || self.text_edit.is_some();
+        if ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_F) {
+            self.open_search();
+        } else if ctrl && !typing && self.rl.is_key_pressed(KeyboardKey::KEY_Z) {
+            if shift { self.redo() } else { self.undo() }
+        } else if ctrl && !typing && self.rl.is_key_pressed(KeyboardKey::KEY_Y) {
+            self.redo();
+        } else if self.search.is_some() {
+            self.update_search();
+        } else if self.naming.is_some() {
+            self.update_naming();
+        } else if self.export_prompt.is_some() {
+            self.update_export_prompt();
+        } else if self.save_prompt.is_some() {
+            self.update_save_prompt();
+        } else if self.text_edit.is_some() {
+            self.update_text_edit();
+        } else if ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_S) {
+            self.save_circuit();
+        } else if ctrl && self.settings.edit_mode && self.rl.is_key_pressed(KeyboardKey::KEY_G) {
+            self.make_ic_from_selection();
+        } else if ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_P) {
+            //shift for just what's on screen
+            self.export_prompt = Some((!shift, DEFAULT_EXPORT_SCALE.to_string()));
+            self.rl.set_exit_key(None);
+        } else if ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_C) {
+            self.copy();
+        } else if ctrl && self.settings.edit_mode && self.rl.is_key_pressed(KeyboardKey::KEY_V) {
+            self.paste();
+        } else if ctrl && self.settings.edit_mode && self.rl.is_key_pressed(KeyboardKey::KEY_D) {
+            self.duplicate();
+        } else if alt && self.settings.edit_mode && !self.selected.is_empty() {
+            let op = [
+                (KeyboardKey::KEY_LEFT, Some(Arrange::Left)),
+                (KeyboardKey::KEY_RIGHT, Some(Arrange::Right)),
+                (KeyboardKey::KEY_UP, Some(Arrange::Top)),
+                (KeyboardKey::KEY_DOWN, Some(Arrange::Bottom)),
+                (KeyboardKey::KEY_C, Some(Arrange::CenterX)),
+                (KeyboardKey::KEY_M, Some(Arrange::CenterY)),
+                (KeyboardKey::KEY_H, Some(Arrange::DistributeX)),
+                (KeyboardKey::KEY_V, Some(Arrange::DistributeY)),
+                (KeyboardKey::KEY_S, None),
+            ]
+            .into_iter()
+            .find(|(key, _)| self.rl.is_key_pressed(*key));
+            match op {
+                Some((_, Some(op))) => self.arrange_selection(op),
+                Some((_, None)) => self.stack_pins(),
+                None => {}
+            }
+        } else if let Some(slot) = SLOT_KEYS.iter().position(|k| self.rl.is_key_pressed(*k)) {
+            if ctrl {
+                let name = match &self.bookmarks[slot] {
+                    Some(b) => b.name.clone(),
+                    None => format!("view {}", slot + 1),
+                };
+                self.naming = Some((slot, name));
+                self.rl.set_exit_key(None);
+            } else {
+                self.recall_bookmark(slot);
+            }
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            if let [comp_i] = self.selected[..] {
+                self.enter_ic(comp_i);
+            }
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            self.exit_ic();
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_HOME) {
+            self.fit_all();
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+            self.zoom_to_selection();
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_E) {
+            self.settings.edit_mode = !self.settings.edit_mode;
+            self.finish_text_edit(true);
+            self.moving = None;
+            self.placing = None;
+            self.wiring = None;
+            self.selected_wire = None;
+        } else if !ctrl && shift && self.rl.is_key_pressed(KeyboardKey::KEY_G) {
+            self.settings.show_grid = !self.settings.show_grid;
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_G) {
+            self.settings.snap_to_grid = !self.settings.snap_to_grid;
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_DELETE) && self.settings.edit_mode {
+            if let Some((comp_i, in_pin)) = self.selected_wire.take() {
+                self.disconnect(comp_i, in_pin);
+            } else if !self.selected.is_empty() {
+                self.delete_comps(self.selected.clone());
+            }
+        } else if !ctrl && shift && self.rl.is_key_pressed(KeyboardKey::KEY_T) {
+            self.settings.theme = self.settings.theme.next();
+            theme::save(self.settings.theme);
+            self.set_status(format!("{} theme", self.settings.theme.name()));
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_T) && self.settings.edit_mode {
+            self.toggle_initial_state();
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_B) && self.settings.edit_mode {
+            self.group_bus(!shift);
+        } else if !ctrl && !alt && self.rl.is_key_pressed(KeyboardKey::KEY_V) {
+            self.settings.gate_style = match self.settings.gate_style {
+                GateStyle::Box => GateStyle::Ieee,
+                GateStyle::Ieee => GateStyle::Box,
+            };
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_W) {
+            self.settings.orthogonal_wires = !self.settings.orthogonal_wires;
+            self.rebuild_caches();
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_I) {
+            self.settings.show_inspector = !self.settings.show_inspector;
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_M) {
+            self.settings.show_minimap = !self.settings.show_minimap;
+        } else if !ctrl && self.rl.is_key_pressed(KeyboardKey::KEY_F) {
             self.rl.toggle_fullscreen();
             if !self.rl.is_window_fullscreen() {
                 self.rl.set_window_size(400, 400);
@@ -264,13 +2401,42 @@ impl State {
         }
         let mouse_pos = self.rl.get_mouse_position();
 
-        
-        if self.rl.is_gesture_detected(Gesture::GESTURE_TAP) {
+        if self.settings.show_minimap && self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.pointer_on_minimap = self
+                .minimap_view()
+                .is_some_and(|view| view.screen.check_collision_point_rec(mouse_pos));
+        }
+        if self.pointer_on_minimap {
+            self.update_minimap(mouse_pos);
+            self.hover = None;
+            return;
+        }
+        if self.update_inspector(mouse_pos) {
+            self.hover = None;
+            return;
+        }
+        if self.settings.edit_mode && self.update_palette(mouse_pos) {
+            self.hover = None;
+            return;
+        }
+        if self.settings.edit_mode && self.rl.is_gesture_detected(Gesture::GESTURE_DOUBLETAP) {
+            let current = self.rl.get_screen_to_world2D(mouse_pos, self.cam);
+            self.finish_text_edit(true);
+            if let Some((comp_i, target)) = self.text_at(current) {
+                self.start_text_edit(comp_i, target);
+            }
+        } else if self.rl.is_gesture_detected(Gesture::GESTURE_TAP) {
+            //clicking away keeps what was typed
+            if self.text_edit.is_some() {
+                self.finish_text_edit(true);
+            }
             let current = self.rl.get_screen_to_world2D(
                 self.rl.get_mouse_position(),
                 self.cam,
             );
-            for i in 0..self.circuit.inputs.len() {
+            //buttons inside an IC are driven by its pins, and in edit mode they get moved instead
+            let inputs = if self.ic_path.is_empty() && !self.settings.edit_mode { self.circuit.inputs.len() } else { 0 };
+            for i in 0..inputs {
                 let i = self.circuit.inputs[i];
                 let comp = &mut self.circuit.components[i];
                 let comp_rect = raylib::math::Rectangle::new(comp.x, comp.y, BUTTON_SIZE, BUTTON_SIZE);
@@ -286,6 +2452,29 @@ impl State {
                     }
                 }
             }
+            let bus = if self.pointer_on_button { None } else { self.bus_at(current) };
+            if let Some(bus_i) = bus {
+                self.toggle_bus(bus_i);
+            } else if !self.pointer_on_button {
+                self.highlight = self.net_at(current);
+                let shift = self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+                match self.hit_test(current) {
+                    Some(Hover::Comp(comp_i)) if shift => {
+                        match self.selected.iter().position(|&i| i == comp_i) {
+                            Some(pos) => {
+                                self.selected.remove(pos);
+                            }
+                            None => self.selected.push(comp_i),
+                        }
+                    }
+                    //keep the selection so it can be dragged as a group
+                    Some(Hover::Comp(comp_i)) if self.settings.edit_mode && self.selected.contains(&comp_i) => {}
+                    Some(Hover::Comp(comp_i)) => self.selected = vec![comp_i],
+                    //shift-dragging a box adds to the selection
+                    _ if shift && self.settings.edit_mode => {}
+                    _ => self.selected.clear(),
+                }
+            }
 
             self.last=Some(current);
         } else if self.rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT) {
@@ -307,20 +2496,70 @@ impl State {
             self.pointer_on_button=false;
             self.last=None;
         }
-        if !self.pointer_on_button {
+        self.update_cam_anim();
+        if self.settings.edit_mode {
+            self.update_edit(mouse_pos);
+        }
+        if !self.pointer_on_button && self.moving.is_none() && self.wiring.is_none() && self.box_select.is_none() {
             self.update_drag(mouse_pos);
             self.update_zoom(mouse_pos);
         }
+        self.update_hover(mouse_pos);
     }
     pub fn draw(&mut self) {
-        const BUTTON_BORDER: f32 = 5.0;
-        const LABEL_SIZE: i32 = 12;
-        const NOTE_SIZE: i32 = 40;
+        if let Some(export) = self.pending_export.take() {
+            self.export_png(export);
+        }
 
+        let tooltip = self.hover.map(|hover| self.tooltip_lines(hover));
+        let inspector = if self.settings.show_inspector { self.inspector_rows() } else { Vec::new() };
+        let inspector_rect = self.inspector_rect(inspector.len());
+        let minimap = if self.settings.show_minimap { self.minimap_view() } else { None };
+        let breadcrumb = if self.ic_path.is_empty() {
+            None
+        } else {
+            let mut names = vec![self.circuit.header.name.clone()];
+            let mut c = &self.circuit;
+            for &comp_i in &self.ic_path {
+                let comp = &c.components[comp_i];
+                c = ic_of(comp);
+                names.push(comp.label.clone().unwrap_or_else(|| c.header.name.clone()));
+            }
+            Some(names.join(" > "))
+        };
+        let search_lines: Option<Vec<String>> = self.search.as_ref().map(|search| {
+            search.hits.iter().take(SEARCH_MAX_SHOWN).map(|&comp_i| {
+                let comp = &self.circ().components[comp_i];
+                format!("{} ({})", self.label_of(comp_i), comp.node_type)
+            }).collect()
+        });
+        let theme = self.settings.theme.colors();
+        let world = World {
+            circuit: circuit_at(&self.circuit, &self.ic_path),
+            settings: &self.settings,
+            comp_labels: &self.comp_labels,
+            in_pin_pos: &self.in_pin_pos,
+            out_pin_pos: &self.out_pin_pos,
+            comp_inputs: &self.comp_inputs,
+            comp_outputs: &self.comp_outputs,
+            ic_pin_names: &self.ic_pin_names,
+            buses: &self.buses,
+            expanded_buses: &self.expanded_buses,
+            highlight: self.highlight,
+            text_edit: self.text_edit.as_ref(),
+        };
+        let overlays = Overlays {
+            selected: &self.selected,
+            selected_wire: self.selected_wire,
+            box_select: self.box_select,
+            wiring: self.wiring,
+            search_hit: self.search.as_ref().and_then(|search| search.hits.get(search.selected).copied()),
+            mouse: self.rl.get_screen_to_world2D(self.rl.get_mouse_position(), self.cam),
+        };
         let rl = &mut self.rl;
         let t = &self.t;
         let mut draw = rl.begin_drawing(t);
-        draw.clear_background(Color::RAYWHITE);
+        draw.clear_background(theme.background);
         let screen_rect = {
             let screen_corner = Vector2::new(draw.get_render_width() as f32, draw.get_render_height() as f32);
             let corner = draw.get_screen_to_world2D(screen_corner, self.cam);
@@ -329,192 +2568,17 @@ impl State {
         };
         {
             let mut draw = draw.begin_mode2D(self.cam);
-            draw.draw_circle(0, 0, 50.0, Color::PINK);
-            let c = &self.circuit;
-            for (comp_i, comp) in c.components.iter().enumerate() {
-                let to_num_in = sls::get_num_inputs(comp);
-                let to_num_out = comp.outputs.len();
-                let to_height = calculate_comp_height(comp.node_type,max(to_num_in, to_num_out));
-                let label = &self.comp_labels[comp_i];
-                let size = draw.measure_text(label, LABEL_SIZE);
-                //TODO actually make sure label is below ic's
-                draw.draw_text(
-                    label,
-                    (comp.x + (MIN_IC_COMP_SIZE / 2.0)) as i32 - (size / 2),
-                    (comp.y + to_height) as i32,
-                    LABEL_SIZE,
-                    Color::BLACK,
-                );
-                //draw wires
-                //wire goes *from* one component *to* this component
-                //plus components have *in*put pins and *out*put pins
-                let to_in_y_offset = calculate_pin_height(to_num_in, to_height);
-                let to_in_y = comp.y + to_in_y_offset;
-                let to_out_y_offset = calculate_pin_height(to_num_out, to_height);
-                let to_out_y = comp.y + to_out_y_offset;
-                for i in 0..to_num_in {
-                    let pin_pos =
-                        Vector2::new(comp.x - PIN_LEN, to_in_y + (PIN_SPACING * i as f32));
-                    let pin_pos_line = Vector2::new(
-                        comp.x - PIN_LEN + PIN_SIZE,
-                        to_in_y + (PIN_SPACING * i as f32),
-                    );
-                    let comp_pos = Vector2::new(comp.x, to_in_y + (PIN_SPACING * i as f32));
-                    if screen_rect.check_collision_point_rec(pin_pos) {
-                        draw.draw_line_ex(pin_pos_line, comp_pos, WIRE_THICKNES, PIN_COLOR);
-                        draw.draw_circle_lines_v(pin_pos, PIN_SIZE, PIN_COLOR);
-                    }
-                }
-                if comp.node_type != NodeType::LIGHT_BULB {
-                    for i in 0..to_num_out {
-                        let comp_size = get_comp_size(comp);
-                        let comp_right = comp.x+comp_size;
-                        let pin_pos = Vector2::new(
-                            comp_right + PIN_LEN,
-                            to_out_y + (PIN_SPACING * i as f32),
-                        );
-                        let pin_pos_line = Vector2::new(
-                            (comp_right + PIN_LEN) - PIN_SIZE,
-                            to_out_y + (PIN_SPACING * i as f32),
-                        );
-                        let comp_pos = Vector2::new(
-                            comp_right,
-                            to_out_y + (PIN_SPACING * i as f32),
-                        );
-                        if screen_rect.check_collision_point_rec(pin_pos) {
-                            draw.draw_line_ex(pin_pos_line, comp_pos, WIRE_THICKNES, PIN_COLOR);
-                            draw.draw_circle_lines_v(pin_pos, PIN_SIZE, PIN_COLOR);
-                        }
-                    }
-                }
-                for input in &self.comp_inputs[comp_i] {
-                    let on = match comp.input_states.get(input.in_pin){Some(s)=>*s,None=>panic!("tried to get {} of {:#?}",&input.in_pin,comp)};
-                    let color = if on { ON_COLOR } else { OFF_COLOR };
-                    let p1 = self.out_pin_pos[input.other_comp][input.other_pin];
-                    let p2 = self.in_pin_pos[comp_i][input.in_pin];
-                    if screen_rect.check_collision_point_rec(p1)||screen_rect.check_collision_point_rec(p2) {
-                        draw.draw_line_ex(p1, p2, WIRE_THICKNES, color);
-                    }
-                }
-                match comp.node_type {
-                    sls::NodeType::LIGHT_BULB => {
-                        let b: bool = comp.outputs[0];
-                        let color = if b { Color::LIGHTGREEN } else { Color::BLACK };
-                        const LIGHT_RADIUS: f32 = COMP_SIZE / 2.0;
-                        let pos = Vector2::new(comp.x + LIGHT_RADIUS, comp.y + LIGHT_RADIUS);
-                        draw.draw_circle_v(pos, LIGHT_RADIUS, Color::GRAY);
-                        draw.draw_circle_v(pos, LIGHT_RADIUS - BUTTON_BORDER, color);
-                    }
-                    sls::NodeType::PULSE_BUTTON => {
-                        let b: bool = comp.outputs[0];
-                        let color = if b { Color::DARKRED } else { Color::RED };
-                        const BUTTON_RADIUS: f32 = BUTTON_SIZE / 2.0;
-                        let pos = Vector2::new(comp.x + BUTTON_RADIUS, comp.y + BUTTON_RADIUS);
-                        draw.draw_circle_v(pos, BUTTON_RADIUS, Color::ORANGE);
-                        draw.draw_circle_v(pos, BUTTON_RADIUS - BUTTON_BORDER, color);
-                    }
-                    sls::NodeType::TOGGLE_BUTTON => {
-                        let b: bool = comp.outputs[0];
-                        let color = if b { Color::DARKRED } else { Color::RED };
-                        let pos = Vector2::new(comp.x, comp.y);
-                        draw.draw_rectangle_v(
-                            pos,
-                            Vector2::new(BUTTON_SIZE, BUTTON_SIZE),
-                            Color::ORANGE,
-                        );
-                        let pos = Vector2::new(comp.x + BUTTON_BORDER, comp.y + BUTTON_BORDER);
-                        draw.draw_rectangle_v(
-                            pos,
-                            Vector2::new(
-                                BUTTON_SIZE - (BUTTON_BORDER * 2.0),
-                                BUTTON_SIZE - (BUTTON_BORDER * 2.0),
-                            ),
-                            color,
-                        );
-                    }
-                    sls::NodeType::NOTE => {
-                        let text: &str = comp.text.as_ref().expect("text field of NODE");
-                        draw.draw_text(text, comp.x as i32, comp.y as i32, NOTE_SIZE, Color::BLACK);
-                    }
-                    sls::NodeType::SEVEN_SEGMENT_DISPLAY => {
-                        const SEGMENT_LENGTH:f32 = 40.0;
-                        const SEGMENT_WIDTH:f32 = 5.0;
-
-                        let pos = Vector2::new(comp.x, comp.y);
-                        draw.draw_rectangle_v(pos, Vector2::new(COMP_SIZE, to_height), Color::BLACK);
-                        let x1 = comp.x+(COMP_SIZE/2.0)-(SEGMENT_LENGTH/2.0);
-                        let x2 = comp.x+(COMP_SIZE/2.0)+(SEGMENT_LENGTH/2.0);
-                        let y1 = comp.y;
-                        let y2 = comp.y + (to_height/2.0);
-                        let y3 = comp.y + to_height;
-                        let points = [
-                            Vector2::new(x1,y1),//0
-                            Vector2::new(x2,y1),//1
-                            Vector2::new(x1,y2),//2
-                            Vector2::new(x2,y2),//3
-                            Vector2::new(x1,y3),//4
-                            Vector2::new(x2,y3),//5
-                        ];
-                        //a
-                        draw.draw_line_ex(
-                            points[0],
-                            points[1], 
-                            SEGMENT_WIDTH, if comp.input_states[0] {ON_COLOR} else {OFF_COLOR});
-                        //b
-                        draw.draw_line_ex(
-                            points[1],
-                            points[3], 
-                            SEGMENT_WIDTH, if comp.input_states[1] {ON_COLOR} else {OFF_COLOR});
-                        //c
-                        draw.draw_line_ex(
-                            points[3],
-                            points[5], 
-                            SEGMENT_WIDTH, if comp.input_states[2] {ON_COLOR} else {OFF_COLOR});
-                        //d
-                        draw.draw_line_ex(
-                            points[4],
-                            points[5], 
-                            SEGMENT_WIDTH, if comp.input_states[3] {ON_COLOR} else {OFF_COLOR});
-                        //e
-                        draw.draw_line_ex(
-                            points[2],
-                            points[4], 
-                            SEGMENT_WIDTH, if comp.input_states[4] {ON_COLOR} else {OFF_COLOR});
-                        //f
-                        draw.draw_line_ex(
-                            points[0],
-                            points[2], 
-                            SEGMENT_WIDTH, if comp.input_states[5] {ON_COLOR} else {OFF_COLOR});
-                        //g
-                        draw.draw_line_ex(
-                            points[2],
-                            points[3],
-                            SEGMENT_WIDTH, if comp.input_states[6] {ON_COLOR} else {OFF_COLOR});
-
-                    }
-                    NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => {}
-                    _ => {
-                        let color = if let Some(ic) = &comp.ic_instance {
-                            if ic.comps_changed {Color::VIOLET}else{Color::DARKRED}
-                        } else {
-                            Color::GRAY
-                        };
-                        let pos = Vector2::new(comp.x, comp.y);
-                        draw.draw_rectangle_v(pos, Vector2::new(COMP_SIZE, to_height), color);
-                    }
-                }
-                
-            }
+            draw_world(&mut draw, &world, Some(&overlays), self.cam, screen_rect, theme);
         }
         draw.draw_fps(0, 0);
-        draw.draw_text(&format!("ran {} times",self.run_times), 0, 10, 12, Color::BLACK);
+        draw.draw_text(&format!("ran {} times",self.run_times), 0, 10, 12, theme.text);
         if draw.get_touch_point_count()>=2 {
             let tp1 = draw.get_touch_position(0);
             let tp2 = draw.get_touch_position(1);
             //draw.draw_circle_v(tp1, 5.0, Color::PINK);
             //draw.draw_circle_v(tp2, 5.0, Color::PURPLE);
             let mid = (tp1+tp2).scale_by(0.5);
-            draw.draw_circle_v(mid, 2.0, Color::BLUE);
+            draw.draw_circle_v(mid, 2.0, theme.select);
         }
         const BOUNDS_W:f32 = 0.4;
         const BOUNDS_H:f32 = 0.1;
@@ -527,6 +2591,132 @@ impl State {
         // draw.gui_slider(Rectangle::new(w-bw, h-bh, bw, bh),"Tick Speed","",&mut tick_speed, 0.005,0.010);
         self.tick_rate = tick_speed as f64;
 
+        if self.settings.edit_mode {
+            let rect = Rectangle::new(w - PALETTE_W, PALETTE_TOP, PALETTE_W, h - PALETTE_TOP);
+            draw.draw_rectangle_rec(rect, theme.panel);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.panel_line);
+            {
+                let mut draw = draw.begin_scissor_mode(rect.x as i32, rect.y as i32, rect.width as i32, rect.height as i32);
+                for (i, item) in self.palette.iter().enumerate() {
+                    let row_y = rect.y + i as f32 * PALETTE_ROW - self.palette_scroll;
+                    if Some(i) == self.placing {
+                        draw.draw_rectangle_rec(Rectangle::new(rect.x, row_y, PALETTE_W, PALETTE_ROW), theme.row_selected);
+                    }
+                    let color = if matches!(item, PaletteItem::Ic(..)) { theme.palette_ic } else { theme.text };
+                    draw.draw_text(&item.name(), rect.x as i32 + 6, row_y as i32 + 4, LABEL_SIZE, color);
+                }
+            }
+            if let Some(item) = self.placing {
+                let mouse = draw.get_mouse_position();
+                let ghost = Rectangle::new(mouse.x, mouse.y, COMP_SIZE * self.cam.zoom, COMP_SIZE * self.cam.zoom);
+                draw.draw_rectangle_rec(ghost, theme.ghost);
+                draw.draw_text(&self.palette[item].name(), mouse.x as i32, (mouse.y - 14.0) as i32, LABEL_SIZE, theme.panel_line);
+            }
+        }
+        if !inspector.is_empty() {
+            draw.draw_rectangle_rec(inspector_rect, theme.panel);
+            draw.draw_rectangle_lines_ex(inspector_rect, 1.0, theme.panel_line);
+            for (i, (text, action)) in inspector.iter().enumerate() {
+                let color = if action.is_some() { theme.link } else { theme.text };
+                let y = inspector_rect.y + 4.0 + i as f32 * INSPECTOR_ROW;
+                draw.draw_text(text, inspector_rect.x as i32 + 4, y as i32, LABEL_SIZE, color);
+            }
+        }
+        if let Some(view) = minimap {
+            draw.draw_rectangle_rec(view.screen, theme.panel);
+            for comp in &circuit_at(&self.circuit, &self.ic_path).components {
+                let active = comp.outputs.iter().any(|b| *b) || comp.input_states.iter().any(|b| *b);
+                let color = if active { theme.wire_on } else { theme.pin };
+                draw.draw_rectangle_rec(view.rect_to_screen(get_comp_rect(comp)), color);
+            }
+            let viewport = view.rect_to_screen(screen_rect);
+            draw.draw_rectangle_lines_ex(viewport, 1.0, theme.viewport);
+            draw.draw_rectangle_lines_ex(view.screen, 1.0, theme.panel_line);
+        }
+        if let (Some(search), Some(lines)) = (&self.search, search_lines) {
+            const SEARCH_W: f32 = 300.0;
+            let line_h = SEARCH_SIZE as f32 + 4.0;
+            let x = 10.0;
+            let y = 42.0;
+            let more = search.hits.len().saturating_sub(lines.len());
+            let rows = 1 + lines.len() + if more > 0 { 1 } else { 0 };
+            let rect = Rectangle::new(x, y, SEARCH_W, line_h * rows as f32 + 4.0);
+            draw.draw_rectangle_rec(rect, theme.panel);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.panel_line);
+            draw.draw_text(&format!("find: {}_", search.query), x as i32 + 4, y as i32 + 4, SEARCH_SIZE, theme.text);
+            for (i, line) in lines.iter().enumerate() {
+                let row_y = y + 2.0 + line_h * (i + 1) as f32;
+                if i == search.selected {
+                    draw.draw_rectangle_rec(Rectangle::new(x + 1.0, row_y, SEARCH_W - 2.0, line_h), theme.row_selected);
+                }
+                draw.draw_text(line, x as i32 + 4, row_y as i32 + 2, SEARCH_SIZE, theme.text);
+            }
+            if more > 0 {
+                let row_y = y + 2.0 + line_h * (lines.len() + 1) as f32;
+                draw.draw_text(&format!("...{} more", more), x as i32 + 4, row_y as i32 + 2, SEARCH_SIZE, theme.faint_text);
+            }
+        }
+        if self.settings.edit_mode {
+            let text = if self.settings.snap_to_grid { "EDIT (snap)" } else { "EDIT" };
+            draw.draw_text(text, 0, 22, SEARCH_SIZE, theme.status);
+        }
+        if let Some(breadcrumb) = &breadcrumb {
+            let size = draw.measure_text(breadcrumb, SEARCH_SIZE);
+            draw.draw_text(breadcrumb, (w as i32 - size) / 2, 4, SEARCH_SIZE, theme.link);
+        }
+        if let Some((slot, name)) = &self.naming {
+            let text = format!("name view {}: {}_", slot + 1, name);
+            let size = draw.measure_text(&text, SEARCH_SIZE);
+            let rect = Rectangle::new((w - size as f32) / 2.0 - 6.0, h / 2.0 - 14.0, size as f32 + 12.0, SEARCH_SIZE as f32 + 12.0);
+            draw.draw_rectangle_rec(rect, theme.panel);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.panel_line);
+            draw.draw_text(&text, rect.x as i32 + 6, rect.y as i32 + 6, SEARCH_SIZE, theme.text);
+        }
+        if let Some(path) = &self.save_prompt {
+            let text = format!("save circuit as: {}_", path);
+            let size = draw.measure_text(&text, SEARCH_SIZE);
+            let rect = Rectangle::new((w - size as f32) / 2.0 - 6.0, h / 2.0 - 14.0, size as f32 + 12.0, SEARCH_SIZE as f32 + 12.0);
+            draw.draw_rectangle_rec(rect, theme.panel);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.panel_line);
+            draw.draw_text(&text, rect.x as i32 + 6, rect.y as i32 + 6, SEARCH_SIZE, theme.text);
+        }
+        if let Some((whole, scale)) = &self.export_prompt {
+            let text = format!("export {} as png at scale: {}_", if *whole { "circuit" } else { "view" }, scale);
+            let size = draw.measure_text(&text, SEARCH_SIZE);
+            let rect = Rectangle::new((w - size as f32) / 2.0 - 6.0, h / 2.0 - 14.0, size as f32 + 12.0, SEARCH_SIZE as f32 + 12.0);
+            draw.draw_rectangle_rec(rect, theme.panel);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.panel_line);
+            draw.draw_text(&text, rect.x as i32 + 6, rect.y as i32 + 6, SEARCH_SIZE, theme.text);
+        }
+        if let Some((msg, since)) = &self.status {
+            if since.elapsed().as_secs_f32() < STATUS_SECS {
+                let size = draw.measure_text(msg, SEARCH_SIZE);
+                draw.draw_text(msg, (w as i32 - size) / 2, h as i32 - SEARCH_SIZE - 8, SEARCH_SIZE, theme.status);
+            }
+        }
+        if let Some(lines) = tooltip {
+            let line_h = TOOLTIP_SIZE as f32 + 2.0;
+            let text_w = lines.iter().map(|l| draw.measure_text(l, TOOLTIP_SIZE)).max().unwrap_or(0) as f32;
+            let box_w = text_w + TOOLTIP_PADDING * 2.0;
+            let box_h = line_h * lines.len() as f32 + TOOLTIP_PADDING * 2.0;
+            //keep it next to the pointer but on screen
+            let mouse = draw.get_mouse_position();
+            let x = (mouse.x + 16.0).min(w - box_w).max(0.0);
+            let y = (mouse.y + 16.0).min(h - box_h).max(0.0);
+            let rect = Rectangle::new(x, y, box_w, box_h);
+            draw.draw_rectangle_rec(rect, theme.tooltip);
+            draw.draw_rectangle_lines_ex(rect, 1.0, theme.tooltip_line);
+            for (i, line) in lines.iter().enumerate() {
+                draw.draw_text(
+                    line,
+                    (x + TOOLTIP_PADDING) as i32,
+                    (y + TOOLTIP_PADDING + line_h * i as f32) as i32,
+                    TOOLTIP_SIZE,
+                    theme.tooltip_text,
+                );
+            }
+        }
+
         //draw.gui_window_box(
         //    Rectangle::new(0.0, 0.0, 70.0, 70.0),
         //    Some(c"To the window to the wall"),
@@ -534,3 +2724,16 @@ impl State {
         //draw.gui_label(Rectangle::new(0.0, 50.0, 50.0, 20.0), Some(c"ewwo world"));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bus_values_in_hex() {
+        assert_eq!(bus_hex(&[false, false, false]), "0x0");
+        assert_eq!(bus_hex(&[true, true, true, true]), "0xF");
+        assert_eq!(bus_hex(&[true, false, false, false, true]), "0x11");
+        assert_eq!(bus_hex(&[false, true, false, true, false, true, false, true]), "0xAA");
+    }
+}
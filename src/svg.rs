@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use raylib::color::Color;
+use raylib::math::{Rectangle, Vector2};
+use slslib::sls::{self, Circuit, NodeType};
+
+use crate::edit;
+use crate::shapes::{self, GateStyle};
+use crate::state::{
+    bounds_of, calculate_pin_pos, comp_label, get_comp_rect, ic_of, wire_route, BUTTON_BORDER, BUTTON_SIZE,
+    COMP_SIZE, DEFAULT_NOTE_SIZE, LABEL_SIZE, MIN_IC_COMP_SIZE, PIN_SIZE, WIRE_THICKNES,
+};
+use crate::theme::{Theme, ThemeKind};
+
+const MARGIN: f32 = 20.0;
+const FONT: &str = "monospace";
+const PIN_NAME_SIZE: i32 = 8;
+
+pub struct SvgOptions {
+    //color wires, buttons, bulbs and displays by their current state
+    pub states: bool,
+    pub orthogonal_wires: bool,
+    pub gate_style: GateStyle,
+    pub theme: ThemeKind,
+    pub note_size: i32,
+}
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { states: false, orthogonal_wires: true, gate_style: GateStyle::Box, theme: ThemeKind::Light, note_size: DEFAULT_NOTE_SIZE }
+    }
+}
+
+//` fill="#rrggbb"` with an opacity if it's see-through
+fn paint(attr: &str, color: Color) -> String {
+    let mut s = format!(" {}=\"#{:02x}{:02x}{:02x}\"", attr, color.r, color.g, color.b);
+    if color.a < 255 {
+        let _ = write!(s, " {}-opacity=\"{:.3}\"", attr, color.a as f32 / 255.0);
+    }
+    s
+}
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+fn points(path: &[Vector2]) -> String {
+    path.iter().map(|p| format!("{:.1},{:.1}", p.x, p.y)).collect::<Vec<_>>().join(" ")
+}
+fn rect(out: &mut String, r: Rectangle, fill: Color, stroke: Option<Color>) {
+    let _ = write!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"{}", r.x, r.y, r.width, r.height, paint("fill", fill));
+    if let Some(stroke) = stroke {
+        let _ = write!(out, "{} stroke-width=\"2\"", paint("stroke", stroke));
+    }
+    out.push_str("/>\n");
+}
+fn circle(out: &mut String, center: Vector2, radius: f32, fill: Option<Color>, stroke: Option<Color>) {
+    let _ = write!(out, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"", center.x, center.y, radius);
+    out.push_str(&fill.map_or(" fill=\"none\"".to_string(), |fill| paint("fill", fill)));
+    if let Some(stroke) = stroke {
+        let _ = write!(out, "{} stroke-width=\"1\"", paint("stroke", stroke));
+    }
+    out.push_str("/>\n");
+}
+//text with its top left (or top middle/right) at x, y like raylib draws it
+fn text(out: &mut String, text: &str, x: f32, y: f32, size: i32, anchor: &str, color: Color) {
+    for (i, line) in text.lines().enumerate() {
+        let _ = writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\" font-family=\"{}\" text-anchor=\"{}\" dominant-baseline=\"hanging\"{}>{}</text>",
+            x,
+            y + (i as i32 * size) as f32,
+            size,
+            FONT,
+            anchor,
+            paint("fill", color),
+            escape(line)
+        );
+    }
+}
+fn component(out: &mut String, comp: &sls::Component, in_pins: &[Vector2], out_pins: &[Vector2], options: &SvgOptions, theme: &Theme) {
+    let body = Rectangle::new(comp.x, comp.y, COMP_SIZE, get_comp_rect(comp).height);
+    let lit = |b: bool| options.states && b;
+    match comp.node_type {
+        NodeType::LIGHT_BULB => {
+            let radius = COMP_SIZE / 2.0;
+            let center = Vector2::new(comp.x + radius, comp.y + radius);
+            circle(out, center, radius, Some(theme.bulb_rim), None);
+            let color = if lit(comp.outputs[0]) { theme.bulb_on } else { theme.bulb_off };
+            circle(out, center, radius - BUTTON_BORDER, Some(color), None);
+        }
+        NodeType::PULSE_BUTTON => {
+            let radius = BUTTON_SIZE / 2.0;
+            let center = Vector2::new(comp.x + radius, comp.y + radius);
+            circle(out, center, radius, Some(theme.button_rim), None);
+            let color = if lit(comp.outputs[0]) { theme.button_on } else { theme.button_off };
+            circle(out, center, radius - BUTTON_BORDER, Some(color), None);
+        }
+        NodeType::TOGGLE_BUTTON => {
+            rect(out, Rectangle::new(comp.x, comp.y, BUTTON_SIZE, BUTTON_SIZE), theme.button_rim, None);
+            let color = if lit(comp.outputs[0]) { theme.button_on } else { theme.button_off };
+            let inner = BUTTON_SIZE - BUTTON_BORDER * 2.0;
+            rect(out, Rectangle::new(comp.x + BUTTON_BORDER, comp.y + BUTTON_BORDER, inner, inner), color, None);
+        }
+        NodeType::NOTE => {
+            text(out, comp.text.as_deref().unwrap_or(""), comp.x, comp.y, options.note_size, "start", theme.text);
+        }
+        NodeType::SEVEN_SEGMENT_DISPLAY => {
+            rect(out, body, theme.display, None);
+            let (segments, (dp, dp_radius)) = shapes::seven_segment_layout(body);
+            let on = |i: usize| lit(comp.input_states.get(i).copied().unwrap_or(false));
+            for (i, segment) in segments.iter().enumerate() {
+                let color = if on(i) { theme.segment_on } else { theme.segment_off };
+                let _ = writeln!(out, "<polygon points=\"{}\"{}/>", points(segment), paint("fill", color));
+            }
+            if comp.input_states.len() >= 8 {
+                circle(out, dp, dp_radius, Some(if on(7) { theme.segment_on } else { theme.segment_off }), None);
+            }
+        }
+        NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => {
+            rect(out, body, theme.gate_fill, Some(theme.gate_line));
+            let half = shapes::DECODER_PIN_SIZE as f32 / 2.0;
+            for (pin, name) in in_pins.iter().zip(shapes::DECODER_INPUTS) {
+                text(out, name, body.x + 3.0, pin.y - half, shapes::DECODER_PIN_SIZE, "start", theme.gate_line);
+            }
+            for (pin, name) in out_pins.iter().zip(shapes::DECODER_OUTPUTS) {
+                text(out, name, body.x + body.width - 3.0, pin.y - half, shapes::DECODER_PIN_SIZE, "end", theme.gate_line);
+            }
+            text(out, "7seg", body.x + body.width / 2.0, body.y + 2.0, shapes::DECODER_PIN_SIZE, "middle", theme.gate_line);
+        }
+        node_type if shapes::is_gate(node_type) => {
+            let layout = shapes::gate_layout(node_type, body, options.gate_style);
+            match options.gate_style {
+                GateStyle::Box => {
+                    rect(out, layout.body, theme.gate_fill, Some(theme.gate_line));
+                    let y = layout.center.y - shapes::SYMBOL_SIZE as f32 / 2.0;
+                    text(out, shapes::symbol(node_type), layout.center.x, y, shapes::SYMBOL_SIZE, "middle", theme.gate_line);
+                }
+                GateStyle::Ieee => {
+                    if let Some(back) = &layout.back {
+                        let _ = writeln!(out, "<polyline points=\"{}\" fill=\"none\"{} stroke-width=\"2\"/>", points(back), paint("stroke", theme.gate_line));
+                    }
+                    let _ = writeln!(
+                        out,
+                        "<polygon points=\"{}\"{}{} stroke-width=\"2\"/>",
+                        points(&layout.outline),
+                        paint("fill", theme.gate_fill),
+                        paint("stroke", theme.gate_line)
+                    );
+                }
+            }
+            if let Some(center) = layout.bubble {
+                circle(out, center, shapes::BUBBLE_RADIUS, Some(theme.gate_fill), Some(theme.gate_line));
+            }
+        }
+        _ => {
+            let color = if comp.ic_instance.is_some() { theme.ic } else { theme.gate_fill };
+            rect(out, body, color, None);
+            if comp.ic_instance.is_some() {
+                let ic = ic_of(comp);
+                let (ins, outs) = edit::ic_pin_comps(ic);
+                let name = |i: &usize| ic.components[*i].label.clone().unwrap_or_default();
+                let half = PIN_NAME_SIZE as f32 / 2.0;
+                for (pin, i) in in_pins.iter().zip(&ins) {
+                    text(out, &name(i), body.x + 2.0, pin.y - half, PIN_NAME_SIZE, "start", theme.ic_text);
+                }
+                for (pin, i) in out_pins.iter().zip(&outs) {
+                    text(out, &name(i), body.x + body.width - 2.0, pin.y - half, PIN_NAME_SIZE, "end", theme.ic_text);
+                }
+            }
+        }
+    }
+}
+//the circuit as an SVG document, laid out the way the viewer draws it
+pub fn circuit_svg(c: &Circuit, options: &SvgOptions) -> String {
+    let theme = options.theme.colors();
+    let pins: Vec<(Vec<Vector2>, Vec<Vector2>)> = c.components.iter().map(calculate_pin_pos).collect();
+    let rects: Vec<Rectangle> = c.components.iter().map(get_comp_rect).collect();
+    let bounds = bounds_of(c.components.iter()).unwrap_or(Rectangle::new(0.0, 0.0, 0.0, 0.0));
+    let view = Rectangle::new(bounds.x - MARGIN, bounds.y - MARGIN, bounds.width + MARGIN * 2.0, bounds.height + MARGIN * 2.0);
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.1} {:.1} {:.1} {:.1}\" width=\"{:.0}\" height=\"{:.0}\">",
+        view.x, view.y, view.width, view.height, view.width, view.height
+    );
+    let _ = writeln!(out, "<title>{}</title>", escape(&c.header.name));
+    rect(&mut out, view, theme.background, None);
+    //wires under the components, like on screen
+    out.push_str("<g fill=\"none\" stroke-linejoin=\"round\">\n");
+    for (comp_i, comp) in c.components.iter().enumerate() {
+        for input in &comp.inputs {
+            let Some(src) = c.components.iter().position(|other| other.get_id() == &input.other_id) else { continue };
+            let from = pins[src].1[input.other_pin];
+            let to = pins[comp_i].0[input.in_pin];
+            let route = wire_route(options.orthogonal_wires, &rects, src, comp_i, from, to);
+            let on = options.states && comp.input_states.get(input.in_pin).copied().unwrap_or(false);
+            let color = if on { theme.wire_on } else { theme.wire_off };
+            let _ = writeln!(out, "<polyline points=\"{}\"{} stroke-width=\"{}\"/>", points(&route), paint("stroke", color), WIRE_THICKNES);
+        }
+    }
+    out.push_str("</g>\n");
+    for (comp, (in_pins, out_pins)) in c.components.iter().zip(&pins) {
+        let size = get_comp_rect(comp).width;
+        let stubs = in_pins.iter().map(|p| (*p, Vector2::new(comp.x, p.y)));
+        //bulbs only have an output for the simulation
+        let out_pins: &[Vector2] = if comp.node_type == NodeType::LIGHT_BULB { &[] } else { out_pins };
+        let stubs = stubs.chain(out_pins.iter().map(|p| (*p, Vector2::new(comp.x + size, p.y))));
+        for (pin, edge) in stubs {
+            let _ = writeln!(
+                out,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"{} stroke-width=\"{}\"/>",
+                pin.x + if pin.x < edge.x { PIN_SIZE } else { -PIN_SIZE },
+                pin.y,
+                edge.x,
+                edge.y,
+                paint("stroke", theme.pin),
+                WIRE_THICKNES
+            );
+            circle(&mut out, pin, PIN_SIZE, None, Some(theme.pin));
+        }
+        component(&mut out, comp, in_pins, out_pins, options, theme);
+        let height = get_comp_rect(comp).height;
+        text(&mut out, &comp_label(comp), comp.x + MIN_IC_COMP_SIZE / 2.0, comp.y + height, LABEL_SIZE, "middle", theme.text);
+    }
+    out.push_str("</svg>\n");
+    out
+}
+//for the command line: reads an .slj and writes its SVG
+pub fn export_file(circuit_path: &str, svg_path: &str, options: &SvgOptions) -> Result<(), String> {
+    let mut c = edit::load_ic(std::path::Path::new(circuit_path))?;
+    //settle the signals the same way the viewer does on startup
+    c.init_circ(None);
+    c.tick(true);
+    std::fs::write(svg_path, circuit_svg(&c, options)).map_err(|e| format!("couldn't write {}: {}", svg_path, e))
+}
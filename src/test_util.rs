@@ -0,0 +1,24 @@
+//fixtures shared by the unit tests
+use serde_json::json;
+use slslib::sls::{self, Circuit, NodeType, ID};
+
+use crate::edit;
+
+//IDs the way the .slj files have them, plain numbers
+pub fn id(n: u64) -> ID {
+    serde_json::from_value(json!(n)).unwrap()
+}
+//a component at the origin with its inputs as (in pin, other pin, other ID)
+pub fn comp(node_type: NodeType, n: u64, inputs: &[(usize, usize, u64)]) -> sls::Component {
+    let mut comp = edit::new_component(node_type, 0.0, 0.0, id(n)).unwrap();
+    for &(in_pin, other_pin, other) in inputs {
+        comp.inputs.push(serde_json::from_value(edit::input_json(in_pin, other_pin, &id(other))).unwrap());
+    }
+    comp
+}
+//the smallest .slj there is, then the components put in
+pub fn circuit(name: &str, comps: Vec<sls::Component>) -> Circuit {
+    let mut c: Circuit = serde_json::from_value(json!({ "header": { "name": name }, "components": [], "inputs": [] })).unwrap();
+    c.components = comps;
+    c
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use raylib::color::Color;
+use serde_json::{json, Value};
+
+//every color the canvas is drawn with
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub pin: Color,
+    pub wire_on: Color,
+    pub wire_off: Color,
+    pub bulb_rim: Color,
+    pub bulb_on: Color,
+    pub bulb_off: Color,
+    pub button_rim: Color,
+    pub button_on: Color,
+    pub button_off: Color,
+    pub display: Color,
+    pub segment_on: Color,
+    //unlit segments stay faintly visible
+    pub segment_off: Color,
+    pub gate_fill: Color,
+    pub gate_line: Color,
+    pub ic: Color,
+    pub ic_changed: Color,
+    pub ic_text: Color,
+    pub bus: Color,
+    pub highlight: Color,
+    //laid over everything that isn't the highlighted net
+    pub dim: Color,
+    pub select: Color,
+    pub grid_minor: Color,
+    pub grid_major: Color,
+    pub panel: Color,
+    pub panel_line: Color,
+    //clickable inspector rows and the breadcrumb
+    pub link: Color,
+    //status messages and the edit mode marker
+    pub status: Color,
+    pub row_selected: Color,
+    pub palette_ic: Color,
+    //the view's outline on the minimap
+    pub viewport: Color,
+    pub box_select: Color,
+    //what's being placed from the palette
+    pub ghost: Color,
+    pub faint_text: Color,
+    pub tooltip: Color,
+    pub tooltip_line: Color,
+    pub tooltip_text: Color,
+}
+#[derive(Clone, Copy, PartialEq)]
+pub enum ThemeKind {
+    Light,
+    Dark,
+    //blue and orange instead of green and black, safe for red-green colorblindness
+    HighContrast,
+}
+const LIGHT: Theme = Theme {
+    background: Color::RAYWHITE,
+    text: Color::BLACK,
+    pin: Color::GRAY,
+    wire_on: Color::GREEN,
+    wire_off: Color::BLACK,
+    bulb_rim: Color::GRAY,
+    bulb_on: Color::LIGHTGREEN,
+    bulb_off: Color::BLACK,
+    button_rim: Color::ORANGE,
+    button_on: Color::DARKRED,
+    button_off: Color::RED,
+    display: Color::BLACK,
+    segment_on: Color::GREEN,
+    segment_off: Color::new(0, 228, 48, 40),
+    gate_fill: Color::LIGHTGRAY,
+    gate_line: Color::DARKGRAY,
+    ic: Color::DARKRED,
+    ic_changed: Color::VIOLET,
+    ic_text: Color::WHITE,
+    bus: Color::DARKBLUE,
+    highlight: Color::ORANGE,
+    dim: Color::new(245, 245, 245, 200),
+    select: Color::BLUE,
+    grid_minor: Color::new(0, 0, 0, 18),
+    grid_major: Color::new(0, 0, 0, 45),
+    panel: Color::new(255, 255, 255, 235),
+    panel_line: Color::DARKGRAY,
+    link: Color::DARKBLUE,
+    status: Color::MAROON,
+    row_selected: Color::SKYBLUE,
+    palette_ic: Color::DARKPURPLE,
+    viewport: Color::RED,
+    box_select: Color::new(0, 121, 241, 40),
+    ghost: Color::new(130, 130, 130, 120),
+    faint_text: Color::GRAY,
+    tooltip: Color::new(255, 255, 224, 240),
+    tooltip_line: Color::DARKGRAY,
+    tooltip_text: Color::BLACK,
+};
+const DARK: Theme = Theme {
+    background: Color::new(30, 30, 34, 255),
+    text: Color::new(220, 220, 220, 255),
+    pin: Color::new(140, 140, 140, 255),
+    wire_on: Color::new(80, 250, 120, 255),
+    wire_off: Color::new(90, 90, 100, 255),
+    bulb_rim: Color::new(120, 120, 120, 255),
+    bulb_on: Color::new(150, 255, 150, 255),
+    bulb_off: Color::new(20, 20, 20, 255),
+    button_rim: Color::new(200, 130, 40, 255),
+    button_on: Color::new(255, 90, 90, 255),
+    button_off: Color::new(110, 30, 30, 255),
+    display: Color::BLACK,
+    segment_on: Color::new(80, 250, 120, 255),
+    segment_off: Color::new(80, 250, 120, 30),
+    gate_fill: Color::new(60, 60, 68, 255),
+    gate_line: Color::new(190, 190, 190, 255),
+    ic: Color::new(110, 40, 50, 255),
+    ic_changed: Color::new(150, 90, 200, 255),
+    ic_text: Color::new(230, 230, 230, 255),
+    bus: Color::new(100, 160, 255, 255),
+    highlight: Color::ORANGE,
+    dim: Color::new(30, 30, 34, 200),
+    select: Color::new(90, 160, 255, 255),
+    grid_minor: Color::new(255, 255, 255, 14),
+    grid_major: Color::new(255, 255, 255, 36),
+    panel: Color::new(45, 45, 50, 235),
+    panel_line: Color::new(120, 120, 120, 255),
+    link: Color::new(120, 180, 255, 255),
+    status: Color::new(255, 130, 120, 255),
+    row_selected: Color::new(60, 90, 140, 255),
+    palette_ic: Color::new(200, 150, 255, 255),
+    viewport: Color::new(255, 90, 90, 255),
+    box_select: Color::new(90, 160, 255, 50),
+    ghost: Color::new(160, 160, 160, 100),
+    faint_text: Color::new(140, 140, 140, 255),
+    tooltip: Color::new(55, 55, 62, 240),
+    tooltip_line: Color::new(140, 140, 140, 255),
+    tooltip_text: Color::new(230, 230, 230, 255),
+};
+//Okabe-Ito blue and orange, black outlines on white
+const HIGH_CONTRAST: Theme = Theme {
+    background: Color::WHITE,
+    text: Color::BLACK,
+    pin: Color::BLACK,
+    wire_on: Color::new(0, 114, 178, 255),
+    wire_off: Color::new(230, 159, 0, 255),
+    bulb_rim: Color::BLACK,
+    bulb_on: Color::new(86, 180, 233, 255),
+    bulb_off: Color::new(230, 159, 0, 255),
+    button_rim: Color::BLACK,
+    button_on: Color::new(0, 114, 178, 255),
+    button_off: Color::new(230, 159, 0, 255),
+    display: Color::BLACK,
+    segment_on: Color::new(86, 180, 233, 255),
+    segment_off: Color::new(86, 180, 233, 40),
+    gate_fill: Color::WHITE,
+    gate_line: Color::BLACK,
+    ic: Color::new(60, 60, 60, 255),
+    ic_changed: Color::new(204, 121, 167, 255),
+    ic_text: Color::WHITE,
+    bus: Color::new(0, 158, 115, 255),
+    highlight: Color::new(213, 94, 0, 255),
+    dim: Color::new(255, 255, 255, 210),
+    select: Color::new(0, 114, 178, 255),
+    grid_minor: Color::new(0, 0, 0, 25),
+    grid_major: Color::new(0, 0, 0, 60),
+    panel: Color::new(255, 255, 255, 245),
+    panel_line: Color::BLACK,
+    link: Color::new(0, 114, 178, 255),
+    status: Color::new(213, 94, 0, 255),
+    row_selected: Color::new(86, 180, 233, 255),
+    palette_ic: Color::new(204, 121, 167, 255),
+    viewport: Color::new(213, 94, 0, 255),
+    box_select: Color::new(0, 114, 178, 40),
+    ghost: Color::new(0, 0, 0, 80),
+    faint_text: Color::new(90, 90, 90, 255),
+    tooltip: Color::WHITE,
+    tooltip_line: Color::BLACK,
+    tooltip_text: Color::BLACK,
+};
+impl ThemeKind {
+    pub fn colors(self) -> &'static Theme {
+        match self {
+            ThemeKind::Light => &LIGHT,
+            ThemeKind::Dark => &DARK,
+            ThemeKind::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+    pub fn next(self) -> ThemeKind {
+        match self {
+            ThemeKind::Light => ThemeKind::Dark,
+            ThemeKind::Dark => ThemeKind::HighContrast,
+            ThemeKind::HighContrast => ThemeKind::Light,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeKind::Light => "light",
+            ThemeKind::Dark => "dark",
+            ThemeKind::HighContrast => "high contrast",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<ThemeKind> {
+        [ThemeKind::Light, ThemeKind::Dark, ThemeKind::HighContrast].into_iter().find(|t| t.name() == name)
+    }
+}
+
+//per user and outside of any checkout: $XDG_CONFIG_HOME, %APPDATA% or ~/.config, else the working directory
+pub fn config_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map_or_else(|| PathBuf::from("."), |dir| dir.join("raylib_sls"))
+}
+//viewer settings, shared by all circuits
+pub fn settings_path() -> PathBuf {
+    config_dir().join("settings.json")
+}
+//the settings file as an object, empty if there's none yet
+fn read_settings() -> Value {
+    let path = settings_path();
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return json!({});
+    };
+    match serde_json::from_str::<Value>(&text) {
+        Ok(settings) if settings.is_object() => settings,
+        Ok(_) => json!({}),
+        Err(e) => {
+            println!("couldn't read {}: {}", path.display(), e);
+            json!({})
+        }
+    }
+}
+//sets one key, whatever else is in there stays
+fn save_setting(key: &str, value: Value) {
+    let path = settings_path();
+    let mut settings = read_settings();
+    settings[key] = value;
+    let text = serde_json::to_string_pretty(&settings).expect("settings to json");
+    if let Err(e) = std::fs::create_dir_all(config_dir()).and_then(|_| std::fs::write(&path, text)) {
+        println!("couldn't save {}: {}", path.display(), e);
+    }
+}
+pub fn load() -> ThemeKind {
+    read_settings()
+        .get("theme")
+        .and_then(Value::as_str)
+        .and_then(ThemeKind::from_name)
+        .unwrap_or(ThemeKind::Light)
+}
+pub fn save(theme: ThemeKind) {
+    save_setting("theme", json!(theme.name()));
+}
+//font size of notes, None until it's been changed
+pub fn load_note_size() -> Option<i32> {
+    read_settings().get("note_size").and_then(Value::as_i64).map(|size| size as i32)
+}
+pub fn save_note_size(size: i32) {
+    save_setting("note_size", json!(size));
+}
@@ -0,0 +1,375 @@
+use std::fmt::Write;
+
+use slslib::sls::{self, Circuit, NodeType};
+
+use crate::edit;
+use crate::state::ic_of;
+
+const DECODER_MODULE: &str = "sls_7seg_decoder";
+//reserved words of IEEE 1364-2005
+const KEYWORDS: &[&str] = &[
+    "always", "and", "assign", "automatic", "begin", "buf", "bufif0", "bufif1", "case", "casex", "casez", "cell",
+    "cmos", "config", "deassign", "default", "defparam", "design", "disable", "edge", "else", "end", "endcase",
+    "endconfig", "endfunction", "endgenerate", "endmodule", "endprimitive", "endspecify", "endtable", "endtask",
+    "event", "for", "force", "forever", "fork", "function", "generate", "genvar", "highz0", "highz1", "if", "ifnone",
+    "incdir", "include", "initial", "inout", "input", "instance", "integer", "join", "large", "liblist", "library",
+    "localparam", "macromodule", "medium", "module", "nand", "negedge", "nmos", "nor", "noshowcancelled", "not",
+    "notif0", "notif1", "or", "output", "parameter", "pmos", "posedge", "primitive", "pull0", "pull1", "pulldown",
+    "pullup", "pulsestyle_ondetect", "pulsestyle_onevent", "rcmos", "real", "realtime", "reg", "release", "repeat",
+    "rnmos", "rpmos", "rtran", "rtranif0", "rtranif1", "scalared", "showcancelled", "signed", "small", "specify",
+    "specparam", "strong0", "strong1", "supply0", "supply1", "table", "task", "time", "tran", "tranif0", "tranif1",
+    "tri", "tri0", "tri1", "triand", "trior", "trireg", "unsigned", "use", "uwire", "vectored", "wait", "wand",
+    "weak0", "weak1", "while", "wire", "wor", "xnor", "xor",
+];
+//hex digits in gfedcba order, the decoder's output pins are a-g
+const DECODER_TABLE: [u8; 16] = [
+    0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f, 0x77, 0x7c, 0x39, 0x5e, 0x79, 0x71,
+];
+
+//something Verilog accepts as an identifier, not necessarily unique
+fn identifier(name: &str, fallback: &str) -> String {
+    let mut id: String = name.trim().chars().map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' }).collect();
+    if id.trim_matches('_').is_empty() {
+        id = fallback.to_string();
+    }
+    if id.starts_with(|ch: char| ch.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    if KEYWORDS.contains(&id.as_str()) {
+        id.push('_');
+    }
+    id
+}
+//name, or name_2, name_3... if it's taken
+fn unique(name: String, taken: &mut Vec<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 1;
+    while taken.contains(&candidate) {
+        n += 1;
+        candidate = format!("{}_{}", name, n);
+    }
+    taken.push(candidate.clone());
+    candidate
+}
+fn primitive(node_type: NodeType) -> Option<&'static str> {
+    match node_type {
+        NodeType::AND => Some("and"),
+        NodeType::OR => Some("or"),
+        NodeType::NOT => Some("not"),
+        NodeType::XOR => Some("xor"),
+        NodeType::NAND => Some("nand"),
+        NodeType::NOR => Some("nor"),
+        NodeType::XNOR => Some("xnor"),
+        _ => None,
+    }
+}
+
+struct Module {
+    //circuit name it came from, identical ICs share one module
+    base: String,
+    name: String,
+    //everything after the module name
+    body: String,
+}
+struct Writer {
+    modules: Vec<Module>,
+    taken: Vec<String>,
+    uses_decoder: bool,
+    //modules with gate-level feedback
+    feedback: Vec<String>,
+}
+//true if an output feeds back into itself, like in a latch made of gates
+fn has_feedback(c: &Circuit) -> bool {
+    let comps = &c.components;
+    let drivers: Vec<Vec<usize>> = comps
+        .iter()
+        .map(|comp| {
+            comp.inputs.iter().filter_map(|input| comps.iter().position(|other| other.get_id() == &input.other_id)).collect()
+        })
+        .collect();
+    //0 not seen, 1 on the current path, 2 done
+    fn visit(i: usize, drivers: &[Vec<usize>], mark: &mut [u8]) -> bool {
+        match mark[i] {
+            1 => return true,
+            2 => return false,
+            _ => {}
+        }
+        mark[i] = 1;
+        let found = drivers[i].iter().any(|&d| visit(d, drivers, mark));
+        mark[i] = 2;
+        found
+    }
+    let mut mark = vec![0; comps.len()];
+    (0..comps.len()).any(|i| visit(i, &drivers, &mut mark))
+}
+impl Writer {
+    //the module for c, reusing one that came out the same
+    fn module(&mut self, c: &Circuit, top: bool) -> String {
+        let base = identifier(&c.header.name, "circuit");
+        let body = self.body(c, top);
+        if let Some(m) = self.modules.iter().find(|m| m.base == base && m.body == body) {
+            return m.name.clone();
+        }
+        let name = unique(base.clone(), &mut self.taken);
+        if has_feedback(c) {
+            self.feedback.push(name.clone());
+        }
+        self.modules.push(Module { base, name: name.clone(), body });
+        name
+    }
+    fn body(&mut self, c: &Circuit, top: bool) -> String {
+        let comps = &c.components;
+        //pins are the IC's buttons and bulbs, the top level also shows its displays and every button
+        let (mut ins, outs) = edit::ic_pin_comps(c);
+        if top {
+            ins = (0..comps.len())
+                .filter(|&i| matches!(comps[i].node_type, NodeType::TOGGLE_BUTTON | NodeType::PULSE_BUTTON))
+                .collect();
+        }
+        let displays: Vec<usize> = match top {
+            true => (0..comps.len()).filter(|&i| comps[i].node_type == NodeType::SEVEN_SEGMENT_DISPLAY).collect(),
+            false => Vec::new(),
+        };
+        let mut taken: Vec<String> = Vec::new();
+        let mut port_name = |i: usize, fallback: String| unique(identifier(&comps[i].label.clone().unwrap_or_default(), &fallback), &mut taken);
+        let in_ports: Vec<String> = ins.iter().enumerate().map(|(k, &i)| port_name(i, format!("in{}", k))).collect();
+        let out_ports: Vec<String> = outs.iter().enumerate().map(|(k, &i)| port_name(i, format!("out{}", k))).collect();
+        let display_ports: Vec<String> = displays.iter().enumerate().map(|(k, &i)| port_name(i, format!("display{}", k))).collect();
+
+        //wires and instances share the ports' namespace, ports were named first so they keep their names
+        //what drives every output pin
+        let nets: Vec<Vec<String>> = comps
+            .iter()
+            .enumerate()
+            .map(|(i, comp)| match ins.iter().position(|&pin| pin == i) {
+                Some(k) => vec![in_ports[k].clone()],
+                //buttons that aren't pins are stuck where they are
+                None if matches!(comp.node_type, NodeType::TOGGLE_BUTTON | NodeType::PULSE_BUTTON) => {
+                    vec![format!("1'b{}", comp.outputs[0] as u8)]
+                }
+                None => (0..comp.outputs.len()).map(|pin| unique(format!("c{}_{}", i, pin), &mut taken)).collect(),
+            })
+            .collect();
+        let instances: Vec<String> = comps
+            .iter()
+            .enumerate()
+            .map(|(i, comp)| match comp.node_type {
+                NodeType::INTEGRATED_CIRCUIT | NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => unique(format!("u{}", i), &mut taken),
+                _ => unique(format!("g{}", i), &mut taken),
+            })
+            .collect();
+        //unconnected inputs read as low, like in the simulation
+        let driver = |comp: &sls::Component, in_pin: usize| -> String {
+            comp.inputs
+                .iter()
+                .find(|input| input.in_pin == in_pin)
+                .and_then(|input| {
+                    let src = comps.iter().position(|other| other.get_id() == &input.other_id)?;
+                    nets[src].get(input.other_pin).cloned()
+                })
+                .unwrap_or_else(|| "1'b0".to_string())
+        };
+
+        let mut out = String::new();
+        let mut ports: Vec<String> = in_ports.iter().map(|p| format!("input wire {}", p)).collect();
+        ports.extend(out_ports.iter().map(|p| format!("output wire {}", p)));
+        for (&i, p) in displays.iter().zip(&display_ports) {
+            ports.push(format!("output wire [{}:0] {}", sls::get_num_inputs(&comps[i]) - 1, p));
+        }
+        let _ = writeln!(out, "(");
+        for (k, port) in ports.iter().enumerate() {
+            let _ = writeln!(out, "    {}{}", port, if k + 1 < ports.len() { "," } else { "" });
+        }
+        let _ = writeln!(out, ");");
+
+        let wires: Vec<&String> = nets
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !matches!(comps[*i].node_type, NodeType::TOGGLE_BUTTON | NodeType::PULSE_BUTTON))
+            .flat_map(|(_, n)| n)
+            .collect();
+        if !wires.is_empty() {
+            let _ = writeln!(out, "    wire {};", wires.iter().map(|w| w.as_str()).collect::<Vec<_>>().join(", "));
+        }
+        for (i, comp) in comps.iter().enumerate() {
+            let num_in = sls::get_num_inputs(comp);
+            let args: Vec<String> = (0..num_in).map(|pin| driver(comp, pin)).collect();
+            let comment = match &comp.label {
+                Some(label) => format!(" // {}", label.replace('\n', " ")),
+                None => String::new(),
+            };
+            if let Some(gate) = primitive(comp.node_type) {
+                let _ = writeln!(out, "    {} {} ({}, {});{}", gate, instances[i], nets[i][0], args.join(", "), comment);
+                continue;
+            }
+            match comp.node_type {
+                //bulbs pass their input through, inside an IC that's how signals leave it
+                NodeType::LIGHT_BULB => {
+                    let _ = writeln!(out, "    buf {} ({}, {});{}", instances[i], nets[i][0], args[0], comment);
+                }
+                NodeType::SEVEN_SEGMENT_DISPLAY_DECODER => {
+                    self.uses_decoder = true;
+                    let bcd: Vec<&str> = args.iter().rev().map(String::as_str).collect();
+                    let seg: Vec<&str> = nets[i].iter().rev().map(String::as_str).collect();
+                    let _ = writeln!(
+                        out,
+                        "    {} {} (.bcd({{{}}}), .seg({{{}}}));{}",
+                        DECODER_MODULE,
+                        instances[i],
+                        bcd.join(", "),
+                        seg.join(", "),
+                        comment
+                    );
+                }
+                NodeType::INTEGRATED_CIRCUIT => {
+                    let ic = ic_of(comp);
+                    let module = self.module(ic, false);
+                    let (ic_ins, ic_outs) = edit::ic_pin_comps(ic);
+                    //ports in the order the nested module names them, which is pin order
+                    let mut inner: Vec<String> = Vec::new();
+                    let mut inner_name = |j: usize, fallback: String| {
+                        unique(identifier(&ic.components[j].label.clone().unwrap_or_default(), &fallback), &mut inner)
+                    };
+                    let mut conns: Vec<String> = Vec::new();
+                    for (k, &j) in ic_ins.iter().enumerate() {
+                        conns.push(format!(".{}({})", inner_name(j, format!("in{}", k)), args.get(k).map_or("1'b0", String::as_str)));
+                    }
+                    for (k, &j) in ic_outs.iter().enumerate() {
+                        conns.push(format!(".{}({})", inner_name(j, format!("out{}", k)), nets[i].get(k).map_or("", String::as_str)));
+                    }
+                    let _ = writeln!(out, "    {} {} ({});{}", module, instances[i], conns.join(", "), comment);
+                }
+                _ => {}
+            }
+        }
+        for (k, &i) in outs.iter().enumerate() {
+            let _ = writeln!(out, "    assign {} = {};", out_ports[k], nets[i][0]);
+        }
+        for (k, &i) in displays.iter().enumerate() {
+            let segs: Vec<String> = (0..sls::get_num_inputs(&comps[i])).rev().map(|pin| driver(&comps[i], pin)).collect();
+            let _ = writeln!(out, "    assign {} = {{{}}};", display_ports[k], segs.join(", "));
+        }
+        let _ = writeln!(out, "endmodule");
+        out
+    }
+}
+fn decoder_module() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "module {} (", DECODER_MODULE);
+    let _ = writeln!(out, "    input wire [3:0] bcd,");
+    let _ = writeln!(out, "    output reg [6:0] seg");
+    let _ = writeln!(out, ");");
+    let _ = writeln!(out, "    always @(*) begin");
+    let _ = writeln!(out, "        case (bcd)");
+    for (digit, segs) in DECODER_TABLE.iter().enumerate() {
+        let _ = writeln!(out, "            4'h{:x}: seg = 7'b{:07b};", digit, segs);
+    }
+    let _ = writeln!(out, "            default: seg = 7'b0000000;");
+    let _ = writeln!(out, "        endcase");
+    let _ = writeln!(out, "    end");
+    let _ = writeln!(out, "endmodule");
+    out
+}
+
+//the circuit as structural Verilog, one module per distinct IC and the circuit itself last
+//buttons become inputs, bulbs and displays outputs (display bit 0 is segment a)
+//also hands back the modules that have feedback loops
+pub fn circuit_verilog(c: &Circuit) -> (String, Vec<String>) {
+    let mut writer =
+        Writer { modules: Vec::new(), taken: vec![DECODER_MODULE.to_string()], uses_decoder: false, feedback: Vec::new() };
+    let top = writer.module(c, true);
+    let mut out = format!("// {} exported from SLS, top module {}\n", c.header.name, top);
+    if !writer.feedback.is_empty() {
+        let _ = writeln!(
+            out,
+            "// feedback loops in {} come out as combinational loops, most FPGA flows won't synthesize them",
+            writer.feedback.join(", ")
+        );
+    }
+    out.push('\n');
+    if writer.uses_decoder {
+        out.push_str(&decoder_module());
+        out.push('\n');
+    }
+    for m in &writer.modules {
+        let _ = writeln!(out, "module {} {}", m.name, m.body);
+    }
+    (out, writer.feedback)
+}
+//for the command line: reads an .slj and writes its Verilog
+pub fn export_file(circuit_path: &str, verilog_path: &str) -> Result<(), String> {
+    let mut c = edit::load_ic(std::path::Path::new(circuit_path))?;
+    //init_circ fills in the IC instances and pin lists
+    c.init_circ(None);
+    let (text, feedback) = circuit_verilog(&c);
+    std::fs::write(verilog_path, text).map_err(|e| format!("couldn't write {}: {}", verilog_path, e))?;
+    if !feedback.is_empty() {
+        eprintln!("warning: feedback loops in {}, see the top of {}", feedback.join(", "), verilog_path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{circuit, comp};
+
+    fn labeled(label: &str, mut comp: sls::Component) -> sls::Component {
+        comp.label = Some(label.to_string());
+        comp
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier("carry out", "x"), "carry_out");
+        assert_eq!(identifier("3rd", "x"), "_3rd");
+        assert_eq!(identifier("wire", "x"), "wire_");
+        assert_eq!(identifier("parameter", "x"), "parameter_");
+        assert_eq!(identifier(" - ", "in0"), "in0");
+    }
+    #[test]
+    fn unique_names() {
+        let mut taken = Vec::new();
+        assert_eq!(unique("a".to_string(), &mut taken), "a");
+        assert_eq!(unique("a".to_string(), &mut taken), "a_2");
+        assert_eq!(unique("a".to_string(), &mut taken), "a_3");
+    }
+    #[test]
+    fn ports_keep_their_names() {
+        let c = circuit(
+            "top",
+            vec![
+                labeled("g1", comp(NodeType::TOGGLE_BUTTON, 0, &[])),
+                comp(NodeType::NOT, 1, &[(0, 0, 0)]),
+                labeled("c1_0", comp(NodeType::LIGHT_BULB, 2, &[(0, 0, 1)])),
+            ],
+        );
+        let (v, _) = circuit_verilog(&c);
+        assert!(v.contains("input wire g1"), "{}", v);
+        assert!(v.contains("output wire c1_0"), "{}", v);
+        assert!(v.contains("not g1_2 (c1_0_2, g1);"), "{}", v);
+        assert!(v.contains("buf g2 (c2_0, c1_0_2);"), "{}", v);
+        assert!(v.contains("assign c1_0 = c2_0;"), "{}", v);
+        assert!(!v.contains("feedback"), "{}", v);
+    }
+    #[test]
+    fn unconnected_inputs_are_low() {
+        let c = circuit("top", vec![comp(NodeType::AND, 0, &[]), comp(NodeType::LIGHT_BULB, 1, &[(0, 0, 0)])]);
+        let (v, _) = circuit_verilog(&c);
+        assert!(v.contains("and g0 (c0_0, 1'b0, 1'b0);"), "{}", v);
+    }
+    #[test]
+    fn latches_are_flagged() {
+        let c = circuit(
+            "latch",
+            vec![
+                labeled("set", comp(NodeType::TOGGLE_BUTTON, 0, &[])),
+                labeled("reset", comp(NodeType::TOGGLE_BUTTON, 1, &[])),
+                comp(NodeType::NOR, 2, &[(0, 0, 1), (1, 0, 3)]),
+                comp(NodeType::NOR, 3, &[(0, 0, 0), (1, 0, 2)]),
+            ],
+        );
+        assert!(has_feedback(&c));
+        assert!(circuit_verilog(&c).0.contains("feedback loops in latch"));
+    }
+}